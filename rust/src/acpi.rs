@@ -1,5 +1,5 @@
-use crate::{RtcSource, Source, Threshold, common};
-use color_eyre::{Result, eyre::eyre};
+use crate::{RtcSource, Source, Threshold, UcsiSource, Unsupported, common, common::guid, events};
+use color_eyre::{Report, Result, eyre::eyre};
 use std::ffi;
 use time_alarm_service_messages::{
     AcpiTimerId, AcpiTimestamp, AlarmExpiredWakePolicy, AlarmTimerSeconds, TimeAlarmDeviceCapabilities, TimerStatus,
@@ -8,6 +8,8 @@ use time_alarm_service_messages::{
 // This module maps the data returned from call into the C-Library to RUST structures
 unsafe extern "C" {
     fn EvaluateAcpi(input: *const i8, input_len: usize, buffer: *mut u8, buf_len: &mut usize) -> i32;
+    fn InitializeNotification() -> i32;
+    fn WaitForNotification(event: u32) -> u32;
 }
 
 #[derive(num_enum::IntoPrimitive, num_enum::TryFromPrimitive, Debug, Copy, Clone)]
@@ -22,24 +24,21 @@ enum AcpiArgumentType {
 }
 
 const ERROR_SUCCESS: i32 = 0;
-
-mod guid {
-    pub const _SENSOR_CRT_TEMP: uuid::Uuid = uuid::uuid!("218246e7-baf6-45f1-aa13-07e4845256b8");
-    pub const _SENSOR_PROCHOT_TEMP: uuid::Uuid = uuid::uuid!("22dc52d2-fd0b-47ab-95b8-26552f9831a5");
-    pub const FAN_ON_TEMP: uuid::Uuid = uuid::uuid!("ba17b567-c368-48d5-bc6f-a312a41583c1");
-    pub const FAN_RAMP_TEMP: uuid::Uuid = uuid::uuid!("3a62688c-d95b-4d2d-bacc-90d7a5816bcd");
-    pub const FAN_MAX_TEMP: uuid::Uuid = uuid::uuid!("dcb758b1-f0fd-4ec7-b2c0-ef1e2a547b76");
-    pub const FAN_MIN_RPM: uuid::Uuid = uuid::uuid!("db261c77-934b-45e2-9742-256c62badb7a");
-    pub const FAN_MAX_RPM: uuid::Uuid = uuid::uuid!("5cf839df-8be7-42b9-9ac5-3403ca2c8a6a");
-    pub const FAN_CURRENT_RPM: uuid::Uuid = uuid::uuid!("adf95492-0776-4ffc-84f3-b6c8b5269683");
-}
-
-fn cstr_bytes_to_string(raw: &[u8]) -> Result<String> {
-    Ok(ffi::CStr::from_bytes_until_nul(raw)
-        .map_err(|_| color_eyre::eyre::eyre!("Invalid byte slice"))?
-        .to_str()
-        .map_err(|_| color_eyre::eyre::eyre!("String contains invalid characters"))?
-        .to_owned())
+// ACPI.sys fails evaluating a method the firmware doesn't define with STATUS_OBJECT_NAME_NOT_FOUND, which eclib
+// passes on as HRESULT_FROM_WIN32(ERROR_FILE_NOT_FOUND)
+const METHOD_NOT_FOUND: i32 = 0x8007_0002_u32 as i32;
+
+// The ECT0 methods the EC firmware is known to implement are RTMP, TGVR, TSVR, TBST, TBIX, TBTP, TFWS and the
+// time/alarm _Gxx/_Txx ones. The methods below aren't among them: their names were picked by analogy and are
+// provisional until the firmware defines them. They are only ever read, so a wrong name costs a failed call rather
+// than a misdirected write, and firmware without them fails with METHOD_NOT_FOUND, which shows as not supported.
+mod provisional {
+    // Battery trippoint as currently set, the read-back of TBTP
+    pub const GET_BTP: &str = "\\_SB.ECT0.RBTP";
+    // AC or battery power, as ACPI _PSR
+    pub const GET_PSR: &str = "\\_SB.ECT0.TPSR";
+    // Legacy battery information, as ACPI _BIF, for firmware without TBIX
+    pub const GET_BIF: &str = "\\_SB.ECT0.TBIF";
 }
 
 // A user-friendly ACPI input method containing a name and optional arguments
@@ -295,22 +294,25 @@ impl Acpi {
     }
 }
 
-fn acpi_get_var(guid: uuid::Uuid) -> Result<f64> {
+fn acpi_get_var(guid: uuid::Uuid) -> Result<u32> {
     let args = [AcpiMethodArgument::Int(1), AcpiMethodArgument::Guid(guid.to_bytes_le())];
     let output = Acpi::evaluate("\\_SB.ECT0.TGVR", Some(&args))?;
 
     if output.count != 2 {
         Err(eyre!("GET_VAR({guid}) unrecognized output"))
     } else if output.arguments[0].data_32 != 0 {
-        Err(eyre!("GET_VAR({guid}) unknown failure"))
+        // Not Unsupported: the same status comes back while the thermal service is still starting, so it has to go
+        // through the backoff and be retried rather than stop the read for good
+        Err(eyre!(
+            "GET_VAR({guid}) failed with status {}",
+            output.arguments[0].data_32
+        ))
     } else {
-        Ok(f64::from(output.arguments[1].data_32))
+        Ok(output.arguments[1].data_32)
     }
 }
 
-fn acpi_set_var(guid: uuid::Uuid, value: f64) -> Result<()> {
-    let value = value as u32;
-
+fn acpi_set_var(guid: uuid::Uuid, value: u32) -> Result<()> {
     let args = [
         AcpiMethodArgument::Int(1),
         AcpiMethodArgument::Guid(guid.to_bytes_le()),
@@ -328,6 +330,14 @@ fn acpi_set_var(guid: uuid::Uuid, value: f64) -> Result<()> {
 }
 
 impl Source for Acpi {
+    fn is_unsupported(&self, error: &Report) -> bool {
+        error.downcast_ref::<Unsupported>().is_some()
+            || matches!(
+                error.downcast_ref::<AcpiParseError>(),
+                Some(AcpiParseError::EvaluationFailed(METHOD_NOT_FOUND))
+            )
+    }
+
     fn get_temperature(&self) -> Result<f64> {
         let output = Acpi::evaluate("\\_SB.ECT0.RTMP", None)?;
         if output.count != 1 {
@@ -338,27 +348,58 @@ impl Source for Acpi {
     }
 
     fn get_rpm(&self) -> Result<f64> {
-        acpi_get_var(guid::FAN_CURRENT_RPM)
+        acpi_get_var(guid::resolve(guid::FAN_CURRENT_RPM)?).map(f64::from)
+    }
+
+    // The thermal variables describe a single fan, and the ECT0 interface has no method for counting fans or reading
+    // others yet, so only the first fan is reported until the firmware defines them
+    fn get_fan_count(&self) -> Result<u8> {
+        Err(crate::unsupported("The EC interface has no method to count fans"))
+    }
+
+    fn get_fan_rpm(&self, fan: u8) -> Result<f64> {
+        if fan == 0 {
+            self.get_rpm()
+        } else {
+            Err(crate::unsupported("The EC interface only reports the first fan's RPM"))
+        }
     }
 
     fn get_min_rpm(&self) -> Result<f64> {
-        acpi_get_var(guid::FAN_MIN_RPM)
+        acpi_get_var(guid::resolve(guid::FAN_MIN_RPM)?).map(f64::from)
     }
 
     fn get_max_rpm(&self) -> Result<f64> {
-        acpi_get_var(guid::FAN_MAX_RPM)
+        acpi_get_var(guid::resolve(guid::FAN_MAX_RPM)?).map(f64::from)
     }
 
     fn get_threshold(&self, threshold: Threshold) -> Result<f64> {
         match threshold {
-            Threshold::On => Ok(common::dk_to_c(acpi_get_var(guid::FAN_ON_TEMP)? as u32)),
-            Threshold::Ramping => Ok(common::dk_to_c(acpi_get_var(guid::FAN_RAMP_TEMP)? as u32)),
-            Threshold::Max => Ok(common::dk_to_c(acpi_get_var(guid::FAN_MAX_TEMP)? as u32)),
+            Threshold::On => Ok(common::dk_to_c(acpi_get_var(guid::resolve(guid::FAN_ON_TEMP)?)?)),
+            Threshold::Off => Ok(common::dk_to_c(acpi_get_var(guid::resolve(guid::FAN_OFF_TEMP)?)?)),
+            Threshold::Ramping => Ok(common::dk_to_c(acpi_get_var(guid::resolve(guid::FAN_RAMP_TEMP)?)?)),
+            Threshold::Max => Ok(common::dk_to_c(acpi_get_var(guid::resolve(guid::FAN_MAX_TEMP)?)?)),
         }
     }
 
+    fn get_sample_period(&self) -> Result<u32> {
+        acpi_get_var(guid::resolve(guid::SENSOR_SAMPLE_PERIOD)?)
+    }
+
+    fn set_sample_period(&self, period_ms: u32) -> Result<()> {
+        acpi_set_var(guid::resolve(guid::SENSOR_SAMPLE_PERIOD)?, period_ms)
+    }
+
     fn set_rpm(&self, rpm: f64) -> Result<()> {
-        acpi_set_var(guid::FAN_CURRENT_RPM, rpm)
+        acpi_set_var(guid::resolve(guid::FAN_CURRENT_RPM)?, rpm as u32)
+    }
+
+    // The EC interface doesn't define a value that hands the fan back to automatic control, and writing a guessed
+    // one would just set that as the RPM, so refuse rather than send anything
+    fn clear_rpm_override(&self) -> Result<()> {
+        Err(crate::unsupported(
+            "The EC interface has no command to return the fan to automatic control",
+        ))
     }
 
     fn get_bst(&self) -> Result<crate::battery::BstData> {
@@ -369,7 +410,7 @@ impl Source for Acpi {
             Err(eyre!("GET_BST unrecognized output"))
         } else {
             Ok(crate::battery::BstData {
-                state: crate::battery::ChargeState::try_from(data.arguments[0].data_32)?,
+                state: crate::battery::ChargeState::from_bits(data.arguments[0].data_32),
                 rate: data.arguments[1].data_32,
                 capacity: data.arguments[2].data_32,
                 voltage: data.arguments[3].data_32,
@@ -400,20 +441,112 @@ impl Source for Acpi {
                 min_average_interval: data.arguments[13].data_32,
                 capacity_gran1: data.arguments[14].data_32,
                 capacity_gran2: data.arguments[15].data_32,
-                model_number: cstr_bytes_to_string(&data.arguments[16].data)?,
-                serial_number: cstr_bytes_to_string(&data.arguments[17].data)?,
-                battery_type: cstr_bytes_to_string(&data.arguments[18].data)?,
-                oem_info: cstr_bytes_to_string(&data.arguments[19].data)?,
+                model_number: common::str_from_bytes(&data.arguments[16].data),
+                serial_number: common::str_from_bytes(&data.arguments[17].data),
+                battery_type: common::str_from_bytes(&data.arguments[18].data),
+                oem_info: common::str_from_bytes(&data.arguments[19].data),
                 swap_cap: crate::battery::SwapCap::try_from(data.arguments[20].data_32)?,
             })
         }
     }
 
+    fn get_bif(&self) -> Result<crate::battery::BifData> {
+        let data = Acpi::evaluate(provisional::GET_BIF, None)?;
+        // We are expecting 13 arguments
+        if data.count != 13 {
+            Err(eyre!("GET_BIF unrecognized output"))
+        } else {
+            Ok(crate::battery::BifData {
+                power_unit: crate::battery::PowerUnit::try_from(data.arguments[0].data_32)?,
+                design_capacity: data.arguments[1].data_32,
+                last_full_capacity: data.arguments[2].data_32,
+                battery_technology: crate::battery::BatteryTechnology::try_from(data.arguments[3].data_32)?,
+                design_voltage: data.arguments[4].data_32,
+                warning_capacity: data.arguments[5].data_32,
+                low_capacity: data.arguments[6].data_32,
+                capacity_gran1: data.arguments[7].data_32,
+                capacity_gran2: data.arguments[8].data_32,
+                model_number: common::str_from_bytes(&data.arguments[9].data),
+                serial_number: common::str_from_bytes(&data.arguments[10].data),
+                battery_type: common::str_from_bytes(&data.arguments[11].data),
+                oem_info: common::str_from_bytes(&data.arguments[12].data),
+            })
+        }
+    }
+
+    fn get_power_source(&self) -> Result<crate::battery::PowerSource> {
+        crate::battery::PowerSource::try_from(Acpi::evaluate_u32(provisional::GET_PSR, None)?)
+    }
+
     fn set_btp(&self, trippoint: u32) -> Result<()> {
         // No return value is expected according to ACPI spec
         let _ = Acpi::evaluate("\\_SB.ECT0.TBTP", Some(&[AcpiMethodArgument::Int(trippoint)]))?;
         Ok(())
     }
+
+    fn get_btp(&self) -> Result<u32> {
+        Acpi::evaluate_u32(provisional::GET_BTP, None)
+    }
+
+    // The ECT0 interface has no charger methods yet, and a write to a guessed name could hit something else, so
+    // neither direction is sent until the firmware defines them
+    fn get_charge_current_limit(&self) -> Result<u32> {
+        Err(crate::unsupported(
+            "The EC interface has no method to read the charge current limit",
+        ))
+    }
+
+    fn set_charge_current_limit(&self, _limit_ma: u32) -> Result<()> {
+        Err(crate::unsupported(
+            "The EC interface has no method to set the charge current limit",
+        ))
+    }
+
+    fn get_var(&self, guid: uuid::Uuid) -> Result<u32> {
+        acpi_get_var(guid)
+    }
+
+    fn set_var(&self, guid: uuid::Uuid, value: u32) -> Result<()> {
+        acpi_set_var(guid, value)
+    }
+
+    // TFWS, as in uefi/Platforms/QemuSbsaPkg/AcpiTables/ectest.asl, sends EC_CAP_GET_FW_STATE to the EC management
+    // service and returns its 32-bit output. How the version is packed into that is up to the firmware, so it's
+    // shown as is.
+    fn get_firmware_version(&self) -> Result<String> {
+        match Acpi::evaluate_u32("\\_SB.ECT0.TFWS", None)? {
+            // What TFWS returns when FF-A is unavailable or the request failed
+            0 => Err(eyre!("GET_FW_STATE failed")),
+            state => Ok(format!("{state:#010x}")),
+        }
+    }
+
+    fn subscribe_ec_events(&self) -> Option<std::sync::mpsc::Receiver<events::EcEvent>> {
+        // SAFETY: takes no arguments, and returns straight away if notifications are already set up
+        let status = unsafe { InitializeNotification() };
+        if status != 0 {
+            log::warn!("EC notifications unavailable, InitializeNotification failed with status {status}");
+            return None;
+        }
+
+        // WaitForNotification blocks in the driver until the EC raises an event, so it gets a thread of its own
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            loop {
+                // SAFETY: 0 waits for any event, notifications were initialized above
+                let id = unsafe { WaitForNotification(0) };
+                // 0 means the driver request failed, there won't be any more events
+                if id == 0 {
+                    log::warn!("EC notification wait failed, no further events will be received");
+                    break;
+                }
+                if tx.send(events::EcEvent::new(id)).is_err() {
+                    break;
+                }
+            }
+        });
+        Some(rx)
+    }
 }
 
 impl RtcSource for Acpi {
@@ -464,4 +597,76 @@ impl RtcSource for Acpi {
             Some(&[AcpiMethodArgument::Int(timer_id.into())]),
         )?))
     }
+
+    fn set_timer_value(&self, timer_id: AcpiTimerId, seconds: AlarmTimerSeconds) -> Result<()> {
+        // Returns 0 on success, 1 on failure
+        let status = Acpi::evaluate_u32(
+            "\\_SB.ECT0._STV",
+            Some(&[
+                AcpiMethodArgument::Int(timer_id.into()),
+                AcpiMethodArgument::Int(seconds.0),
+            ]),
+        )?;
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(eyre!("SET_TIMER_VALUE failed"))
+        }
+    }
+
+    fn set_expired_timer_wake_policy(&self, timer_id: AcpiTimerId, policy: AlarmExpiredWakePolicy) -> Result<()> {
+        // Returns 0 on success, 1 on failure
+        let status = Acpi::evaluate_u32(
+            "\\_SB.ECT0._STP",
+            Some(&[
+                AcpiMethodArgument::Int(timer_id.into()),
+                AcpiMethodArgument::Int(policy.0),
+            ]),
+        )?;
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(eyre!("SET_TIMER_WAKE_POLICY failed"))
+        }
+    }
+}
+
+// The ECT0 interface has no UCSI methods yet. Calling guessed names would send connector resets and role swaps
+// somewhere unknown, so every command is refused until the firmware defines them.
+impl UcsiSource for Acpi {
+    fn get_connector_count(&self) -> Result<u8> {
+        Err(ucsi_unsupported())
+    }
+
+    fn get_alternate_modes(&self, _connector: u8) -> Result<Vec<crate::ucsi::AltMode>> {
+        Err(ucsi_unsupported())
+    }
+
+    fn get_connector_status(&self, _connector: u8) -> Result<crate::ucsi::ConnectorStatus> {
+        Err(ucsi_unsupported())
+    }
+
+    fn get_current_rdo(&self, _connector: u8) -> Result<crate::ucsi::Rdo> {
+        Err(ucsi_unsupported())
+    }
+
+    fn get_cable_property(&self, _connector: u8) -> Result<crate::ucsi::CableProperty> {
+        Err(ucsi_unsupported())
+    }
+
+    fn connector_reset(&self, _connector: u8, _hard: bool) -> Result<()> {
+        Err(ucsi_unsupported())
+    }
+
+    fn swap_data_role(&self, _connector: u8) -> Result<()> {
+        Err(ucsi_unsupported())
+    }
+
+    fn get_error_status(&self, _connector: u8) -> Result<crate::ucsi::ErrorStatus> {
+        Err(ucsi_unsupported())
+    }
+}
+
+fn ucsi_unsupported() -> Report {
+    crate::unsupported("The EC interface has no UCSI methods")
 }
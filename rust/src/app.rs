@@ -1,6 +1,19 @@
+use crate::bench::Benchmark;
+use crate::cli::Cli;
+use crate::common;
+use crate::compare::Compare;
+use crate::events::Events;
+use crate::explorer::Explorer;
+use crate::overview::Overview;
+use crate::persist::SavedState;
+use crate::proxy::SourceProxy;
+use crate::record::{Recorder, Snapshot};
 use crate::rtc::Rtc;
+use crate::screenshot;
 use crate::thermal::Thermal;
 use crate::ucsi::Ucsi;
+use crate::web::WebServer;
+use crate::widgets::palette::{Outcome, Palette};
 use crate::{Source, battery::Battery};
 
 use color_eyre::Result;
@@ -8,23 +21,35 @@ use color_eyre::Result;
 use ratatui::{
     DefaultTerminal,
     buffer::Buffer,
-    crossterm::event::{self, Event, KeyCode, KeyEventKind},
-    layout::{Constraint, Layout, Rect},
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Flex, Layout, Rect},
     style::{Color, Stylize, palette::tailwind},
     symbols,
     text::Line,
-    widgets::{Block, Padding, Tabs, Widget},
+    widgets::{Block, Clear, Gauge, Padding, Paragraph, Tabs, Widget, Wrap},
 };
 
-use std::marker::PhantomData;
 use std::{
     cell::RefCell,
     collections::BTreeMap,
+    fs,
+    ops::Bound::{Excluded, Unbounded},
+    path::PathBuf,
     rc::Rc,
     time::{Duration, Instant},
 };
 
-use strum::{Display, EnumIter, FromRepr, IntoEnumIterator};
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
+
+const SAVED_TAB_KEY: &str = "tab";
+const SAVED_TWELVE_HOUR_KEY: &str = "twelve_hour";
+
+// Below this size the layout degenerates, so a message is shown instead
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 15;
+
+// Updates still running after this long get a waiting indicator, since they block the whole UI
+const SLOW_UPDATE: Duration = Duration::from_millis(200);
 
 /// Internal trait to be implemented by modules (or Tabs).
 pub(crate) trait Module {
@@ -39,6 +64,34 @@ pub(crate) trait Module {
 
     /// Render the module.
     fn render(&self, area: Rect, buf: &mut Buffer);
+
+    /// Markdown summary of the module's latest data, used for the exported report.
+    fn report(&self) -> String;
+
+    /// The module's headline reading as plain text, copied to the clipboard on request.
+    fn copy_value(&self) -> Option<String> {
+        None
+    }
+
+    /// The uninterpreted values behind the module's display, as label and `Debug` output, for the raw values view.
+    fn raw_values(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    /// Actions offered in the command palette, each run by sending its key to [`Module::handle_event`].
+    fn commands(&self) -> Vec<(&'static str, KeyCode)> {
+        Vec::new()
+    }
+
+    /// Whether one of the module's inputs has keyboard focus, in which case it gets every key.
+    fn has_focus(&self) -> bool {
+        false
+    }
+
+    /// Handle any pending asynchronous notifications, returning whether anything changed.
+    fn poll_notifications(&mut self) -> bool {
+        false
+    }
 }
 
 #[derive(Default, Clone, Copy, PartialEq, Eq)]
@@ -48,9 +101,130 @@ enum AppState {
     Quitting,
 }
 
-#[derive(Default, Clone, Copy, Display, FromRepr, EnumIter, PartialEq, Eq, PartialOrd, Ord, Hash)]
+// Everything the keys and the command palette can do
+#[derive(Clone, Copy)]
+enum Command {
+    Tab(SelectedTab),
+    ModuleKey(SelectedTab, KeyCode),
+    NextTab,
+    PreviousTab,
+    Palette,
+    Freeze,
+    Refresh,
+    Report,
+    Screenshot { ansi: bool },
+    Copy,
+    Transactions,
+    RawValues,
+    ClockFormat,
+    Benchmark,
+    Quit,
+}
+
+// An app-wide command, with the keys that run it and how the footer and palette show it
+struct Binding {
+    keys: &'static [KeyCode],
+    // Footer hint, None if another binding's hint already covers it
+    hint: Option<&'static str>,
+    // Palette entry, None if it makes no sense to run from the palette
+    name: Option<&'static str>,
+    command: Command,
+}
+
+// The single list of app-wide commands, which key handling, the footer and the palette all come from
+const BINDINGS: &[Binding] = &[
+    Binding {
+        keys: &[KeyCode::Char('l'), KeyCode::Right],
+        hint: Some("◄ ► tab"),
+        name: Some("Next tab"),
+        command: Command::NextTab,
+    },
+    Binding {
+        keys: &[KeyCode::Char('h'), KeyCode::Left],
+        hint: None,
+        name: Some("Previous tab"),
+        command: Command::PreviousTab,
+    },
+    Binding {
+        keys: &[KeyCode::Char(' ')],
+        hint: Some("Space freeze"),
+        name: Some("Freeze / resume live updates"),
+        command: Command::Freeze,
+    },
+    Binding {
+        keys: &[KeyCode::F(5)],
+        hint: Some("F5 refresh"),
+        name: Some("Refresh current tab"),
+        command: Command::Refresh,
+    },
+    Binding {
+        keys: &[KeyCode::F(6)],
+        hint: Some("F6 benchmark"),
+        name: Some("Run GET_TMP benchmark"),
+        command: Command::Benchmark,
+    },
+    Binding {
+        keys: &[KeyCode::Char('p')],
+        hint: Some("p report"),
+        name: Some("Export report"),
+        command: Command::Report,
+    },
+    Binding {
+        keys: &[KeyCode::Char('s')],
+        hint: Some("s/S screenshot"),
+        name: Some("Save screenshot (text)"),
+        command: Command::Screenshot { ansi: false },
+    },
+    Binding {
+        keys: &[KeyCode::Char('S')],
+        hint: None,
+        name: Some("Save screenshot (ANSI colors)"),
+        command: Command::Screenshot { ansi: true },
+    },
+    Binding {
+        keys: &[KeyCode::Char('y')],
+        hint: Some("y copy"),
+        name: Some("Copy value to clipboard"),
+        command: Command::Copy,
+    },
+    Binding {
+        keys: &[KeyCode::Char('H')],
+        hint: Some("H 12/24h"),
+        name: Some("Toggle 12/24-hour clock"),
+        command: Command::ClockFormat,
+    },
+    Binding {
+        keys: &[KeyCode::Char('T')],
+        hint: Some("T transactions"),
+        name: Some("Show / hide EC transactions"),
+        command: Command::Transactions,
+    },
+    Binding {
+        keys: &[KeyCode::Char('D')],
+        hint: Some("D raw"),
+        name: Some("Show / hide raw values"),
+        command: Command::RawValues,
+    },
+    Binding {
+        keys: &[KeyCode::Char(':')],
+        hint: Some(": commands"),
+        name: None,
+        command: Command::Palette,
+    },
+    Binding {
+        keys: &[KeyCode::Char('q'), KeyCode::Esc],
+        hint: Some("q quit"),
+        name: Some("Quit"),
+        command: Command::Quit,
+    },
+];
+
+// Saved by name, so adding or reordering tabs doesn't restore the wrong one
+#[derive(Default, Clone, Copy, Display, EnumString, EnumIter, PartialEq, Eq, PartialOrd, Ord, Hash)]
 enum SelectedTab {
     #[default]
+    #[strum(to_string = "Overview")]
+    TabOverview,
     #[strum(to_string = "Battery")]
     TabBattery,
     #[strum(to_string = "Thermal")]
@@ -59,6 +233,12 @@ enum SelectedTab {
     TabRTC,
     #[strum(to_string = "UCSI")]
     TabUCSI,
+    #[strum(to_string = "Explorer")]
+    TabExplorer,
+    #[strum(to_string = "Events")]
+    TabEvents,
+    #[strum(to_string = "Compare")]
+    TabCompare,
 }
 
 /// The main application which holds the state and logic of the application.
@@ -66,57 +246,173 @@ pub struct App<S: Source> {
     state: AppState,
     selected_tab: SelectedTab,
     modules: BTreeMap<SelectedTab, Box<dyn Module>>,
-    phantom: PhantomData<S>,
+    saved_state: SavedState,
+    frozen: bool,
+    source: SourceProxy<S>,
+    firmware_version: Result<String>,
+    status_message: Option<String>,
+    // Set once a tick's update has run past SLOW_UPDATE with reads still to do, so a slow EC doesn't just look
+    // like a hung UI
+    waiting: bool,
+    last_update: Duration,
+    // Created on first use, and kept alive since on X11 the copied text is served by its owner
+    clipboard: Option<arboard::Clipboard>,
+    show_transactions: bool,
+    show_raw: bool,
+    benchmark: Option<Benchmark>,
+    palette: Option<Palette<Command>>,
+    cli: Cli,
 }
 
-impl<S: Source + Clone + 'static> App<S> {
+impl<S: Source + Send + 'static> App<S> {
     /// Construct a new instance of [`App`].
-    pub fn new(source: S) -> Self {
+    pub fn new(source: S, cli: Cli) -> Self {
         let mut modules: BTreeMap<SelectedTab, Box<dyn Module>> = BTreeMap::new();
-        let source = Rc::new(RefCell::new(source));
-
-        let thermal_source = Rc::clone(&source);
-        let battery_source = Rc::clone(&source);
-        let rtc_source = Rc::clone(&source);
+        let source = Rc::new(RefCell::new(SourceProxy::new(source, cli.read_only)));
+
+        let enabled = |tab: SelectedTab| cli.modules.iter().any(|name| *name == tab.to_string().to_lowercase());
+
+        // Boards don't all implement every service, so only create the modules that were asked for
+        for tab in SelectedTab::iter().filter(|tab| enabled(*tab)) {
+            let source = source.borrow().clone();
+            let module: Box<dyn Module> = match tab {
+                SelectedTab::TabOverview => Box::new(Overview::new(source)),
+                SelectedTab::TabBattery => Box::new(
+                    Battery::new(source, cli.bell, cli.battery_graph_interval).with_gauge_thresholds(cli.battery_gauge),
+                ),
+                SelectedTab::TabThermal => Box::new(Thermal::new(source, cli.bell, cli.temp_colors, cli.fan_stall)),
+                SelectedTab::TabRTC => Box::new(Rtc::new(source, cli.confirm.clone())),
+                SelectedTab::TabUCSI => Box::new(Ucsi::new(source, cli.confirm.clone())),
+                SelectedTab::TabExplorer => Box::new(Explorer::new(source)),
+                SelectedTab::TabEvents => Box::new(Events::new(&source)),
+                // Needs a second source, see with_baseline
+                SelectedTab::TabCompare => continue,
+            };
+            modules.insert(tab, module);
+        }
 
-        modules.insert(
-            SelectedTab::TabThermal,
-            Box::new(Thermal::new(thermal_source.borrow().clone())),
-        );
-        modules.insert(SelectedTab::TabRTC, Box::new(Rtc::new(rtc_source.borrow().clone())));
-        modules.insert(SelectedTab::TabUCSI, Box::new(Ucsi::new()));
-        modules.insert(
-            SelectedTab::TabBattery,
-            Box::new(Battery::new(battery_source.borrow().clone())),
-        );
+        // Restore the tab that was open when we last exited, and display preferences
+        let saved_state = SavedState::load();
+        common::set_twelve_hour(saved_state.get(SAVED_TWELVE_HOUR_KEY).unwrap_or(false));
+        common::set_highlight_duration(cli.highlight);
+        common::set_ascii_status(cli.ascii);
+        let selected_tab = saved_state
+            .get::<SelectedTab>(SAVED_TAB_KEY)
+            .filter(|tab| modules.contains_key(tab))
+            .or_else(|| modules.keys().next().copied())
+            .unwrap_or_default();
 
         Self {
             state: Default::default(),
-            selected_tab: Default::default(),
+            selected_tab,
             modules,
-            phantom: PhantomData,
+            saved_state,
+            frozen: false,
+            firmware_version: source.borrow().get_firmware_version(),
+            source: source.borrow().clone(),
+            status_message: None,
+            waiting: false,
+            last_update: Duration::ZERO,
+            clipboard: None,
+            show_transactions: false,
+            show_raw: false,
+            benchmark: None,
+            palette: None,
+            cli,
+        }
+    }
+
+    /// Add a tab comparing the source side by side against `baseline`, named `baseline_name`.
+    pub fn with_baseline<B: Source + 'static>(mut self, baseline: B, baseline_name: String) -> Self {
+        let compare = Compare::new(self.source.clone(), baseline, baseline_name);
+        self.modules.insert(SelectedTab::TabCompare, Box::new(compare));
+        // The tab didn't exist when new restored the saved one, so a saved Compare tab is only restored now
+        if self.saved_state.get::<SelectedTab>(SAVED_TAB_KEY) == Some(SelectedTab::TabCompare) {
+            self.selected_tab = SelectedTab::TabCompare;
         }
+        self
     }
 
     /// Run the application's main loop.
     pub fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
-        let tick_rate = Duration::from_millis(1000);
+        let tick_rate = self.cli.tick;
+        let frame_rate = Duration::from_secs(1) / self.cli.fps;
         let mut last_tick = Instant::now();
+        let mut recorder = self.cli.record.as_deref().map(Recorder::create).transpose()?;
+        let web = self
+            .cli
+            .web
+            .map(|port| WebServer::start(self.cli.web_bind, port))
+            .transpose()?;
+
+        terminal.draw(|frame| frame.render_widget(&self, frame.area()))?;
+        let mut last_frame = Instant::now();
+        let mut needs_redraw = false;
 
         while self.state == AppState::Running {
-            terminal.draw(|frame| frame.render_widget(&self, frame.area()))?;
+            // Only redraw when something changed, and no faster than the frame rate cap
+            if needs_redraw && last_frame.elapsed() >= frame_rate {
+                terminal.draw(|frame| frame.render_widget(&self, frame.area()))?;
+                last_frame = Instant::now();
+                needs_redraw = false;
+            }
+
+            // Notifications are checked once per frame so they don't have to wait for the next tick
+            if !self.frozen && self.poll_notifications() {
+                needs_redraw = true;
+            }
 
-            // Adjust timeout to account for delay from handling input
-            let timeout = tick_rate.saturating_sub(last_tick.elapsed());
+            // Adjust timeout to account for delay from handling input, waking at least once per frame
+            let frame_timeout = if needs_redraw {
+                frame_rate.saturating_sub(last_frame.elapsed())
+            } else {
+                frame_rate
+            };
+            let timeout = tick_rate.saturating_sub(last_tick.elapsed()).min(frame_timeout);
+
+            // The benchmark runs on its own thread, so just keep its progress on screen every frame
+            if let Some(benchmark) = &self.benchmark {
+                if benchmark.is_done() {
+                    self.finish_benchmark();
+                }
+                needs_redraw = true;
+            }
 
             // Handle event if we got it, and only update tab states if we timed out
             if event::poll(timeout)? {
                 self.handle_events()?;
+                needs_redraw = true;
             }
 
             if last_tick.elapsed() >= tick_rate {
-                self.update_tabs();
+                self.source.begin_tick();
+                // While frozen we keep ticking but skip updates, so graphs resume without back-filling the gap
+                if !self.frozen {
+                    let started = Instant::now();
+                    self.update_tabs(&mut terminal, started)?;
+                    self.last_update = started.elapsed();
+                    if self.last_update >= SLOW_UPDATE {
+                        log::warn!("Update took {} ms", self.last_update.as_millis());
+                    }
+                }
+                // Recording and the web snapshot carry on while frozen, and take what the tabs just read from the
+                // tick's cache, so they add no EC traffic of their own
+                if recorder.is_some() || web.is_some() {
+                    let snapshot = Snapshot::read(&self.source);
+                    if let Some(web) = &web {
+                        web.publish(&snapshot);
+                    }
+                    if let Some(active) = &mut recorder
+                        && let Err(e) = active.sample(&snapshot)
+                    {
+                        log::error!("Recording stopped: {e}");
+                        self.status_message = Some(format!("Recording stopped: {e}"));
+                        recorder = None;
+                    }
+                }
+                self.source.end_tick();
                 last_tick = Instant::now();
+                needs_redraw = true;
             }
         }
 
@@ -125,21 +421,91 @@ impl<S: Source + Clone + 'static> App<S> {
 
     fn handle_events(&mut self) -> std::io::Result<()> {
         let evt = event::read()?;
+
+        // The palette takes every key while open, so typing a filter doesn't trigger shortcuts
+        if let Some(palette) = &mut self.palette {
+            match palette.handle_event(&evt) {
+                Outcome::Pending => {}
+                Outcome::Cancel => self.palette = None,
+                Outcome::Run(command) => {
+                    self.palette = None;
+                    self.run_command(command);
+                }
+            }
+            return Ok(());
+        }
+
+        // Likewise a focused input, so typing into it doesn't switch tabs or quit
+        if self
+            .modules
+            .get(&self.selected_tab)
+            .is_some_and(|module| module.has_focus())
+        {
+            self.handle_tab_event(&evt);
+            return Ok(());
+        }
+
         if let Event::Key(key) = evt {
             if key.kind == KeyEventKind::Press {
                 match key.code {
-                    KeyCode::Char('l') | KeyCode::Right => self.next_tab(),
-                    KeyCode::Char('h') | KeyCode::Left => self.previous_tab(),
-                    KeyCode::Char('q') | KeyCode::Esc => self.quit(),
-
-                    // Let the current tab handle event in this case
-                    _ => self.handle_tab_event(&evt),
+                    // Esc closes the overlay first, so it doesn't quit from under it
+                    KeyCode::Esc if self.show_transactions => self.show_transactions = false,
+                    KeyCode::Esc if self.show_raw => self.show_raw = false,
+                    KeyCode::Esc if self.benchmark.is_some() => self.cancel_benchmark(),
+                    code => match BINDINGS.iter().find(|binding| binding.keys.contains(&code)) {
+                        Some(binding) => self.run_command(binding.command),
+                        // Let the current tab handle event in this case
+                        None => self.handle_tab_event(&evt),
+                    },
                 }
             }
         }
         Ok(())
     }
 
+    fn open_palette(&mut self) {
+        let mut commands: Vec<(String, Command)> = BINDINGS
+            .iter()
+            .filter_map(|binding| Some((binding.name?.to_string(), binding.command)))
+            .collect();
+        for (&tab, module) in &self.modules {
+            commands.push((format!("Go to {tab} tab"), Command::Tab(tab)));
+            commands.extend(
+                module
+                    .commands()
+                    .into_iter()
+                    .map(|(name, key)| (format!("{tab}: {name}"), Command::ModuleKey(tab, key))),
+            );
+        }
+        self.palette = Some(Palette::new(commands));
+    }
+
+    fn run_command(&mut self, command: Command) {
+        match command {
+            Command::Tab(tab) => {
+                self.selected_tab = tab;
+                log::info!("Switched to {} tab", self.selected_tab);
+            }
+            Command::ModuleKey(tab, key) => {
+                self.selected_tab = tab;
+                self.handle_tab_event(&Event::Key(KeyEvent::new(key, KeyModifiers::NONE)));
+            }
+            Command::NextTab => self.next_tab(),
+            Command::PreviousTab => self.previous_tab(),
+            Command::Palette => self.open_palette(),
+            Command::Freeze => self.toggle_freeze(),
+            Command::Refresh => self.refresh_tab(),
+            Command::Report => self.export_report(),
+            Command::Screenshot { ansi } => self.save_screenshot(ansi),
+            Command::Copy => self.copy_value(),
+            Command::Transactions => self.show_transactions = !self.show_transactions,
+            Command::RawValues => self.show_raw = !self.show_raw,
+            Command::ClockFormat => self.toggle_clock_format(),
+            Command::Benchmark => self.start_benchmark(),
+            Command::Quit => self.quit(),
+        }
+    }
+
     fn handle_tab_event(&mut self, evt: &Event) {
         self.modules
             .get_mut(&self.selected_tab)
@@ -147,28 +513,193 @@ impl<S: Source + Clone + 'static> App<S> {
             .handle_event(evt);
     }
 
-    fn update_tabs(&mut self) {
+    fn poll_notifications(&mut self) -> bool {
+        // Poll every module, don't stop at the first one that changed
+        let mut changed = false;
         for module in self.modules.values_mut() {
-            module.update();
+            changed |= module.poll_notifications();
         }
+        changed
+    }
+
+    // Reads are synchronous, so nothing else can be drawn until they return. The update is checked between tabs
+    // instead, and once it has run long the UI is redrawn as waiting while the remaining tabs read.
+    fn update_tabs(&mut self, terminal: &mut DefaultTerminal, started: Instant) -> Result<()> {
+        let tabs: Vec<SelectedTab> = self.modules.keys().copied().collect();
+        for (index, tab) in tabs.iter().enumerate() {
+            self.modules.get_mut(tab).expect("Tab must exist").update();
+            let more_to_read = index + 1 < tabs.len() || self.firmware_version.is_err();
+            if !self.waiting && more_to_read && started.elapsed() >= SLOW_UPDATE {
+                self.waiting = true;
+                terminal.draw(|frame| frame.render_widget(&*self, frame.area()))?;
+            }
+        }
+
+        // Firmware version is static, so stop asking once we have it
+        common::retry_static(&mut self.firmware_version, || self.source.get_firmware_version());
+        self.waiting = false;
+        Ok(())
+    }
+
+    // Update the current tab straight away, e.g. after changing something with another tool
+    fn refresh_tab(&mut self) {
+        // An explicit refresh shouldn't be skipped by reads that were paused after failing
+        self.source.clear_backoff();
+        self.modules
+            .get_mut(&self.selected_tab)
+            .expect("Tab must exist")
+            .update();
+        log::info!("Refreshed {} tab", self.selected_tab);
     }
 
     fn next_tab(&mut self) {
-        self.selected_tab = self.selected_tab.next();
+        if let Some((&tab, _)) = self.modules.range((Excluded(self.selected_tab), Unbounded)).next() {
+            self.selected_tab = tab;
+        }
+        log::info!("Switched to {} tab", self.selected_tab);
     }
 
     fn previous_tab(&mut self) {
-        self.selected_tab = self.selected_tab.previous();
+        if let Some((&tab, _)) = self.modules.range(..self.selected_tab).next_back() {
+            self.selected_tab = tab;
+        }
+        log::info!("Switched to {} tab", self.selected_tab);
+    }
+
+    fn toggle_freeze(&mut self) {
+        self.frozen = !self.frozen;
+        log::info!("Live updates {}", if self.frozen { "frozen" } else { "resumed" });
+    }
+
+    fn toggle_clock_format(&mut self) {
+        common::set_twelve_hour(!common::twelve_hour());
+        let message = format!("Showing {}-hour clock", if common::twelve_hour() { 12 } else { 24 });
+        log::info!("{message}");
+        self.status_message = Some(message);
+    }
+
+    fn start_benchmark(&mut self) {
+        if self.benchmark.is_none() {
+            log::info!("Benchmark started: {} GET_TMP requests", self.cli.bench_count);
+            self.benchmark = Some(Benchmark::start(self.source.inner().clone(), self.cli.bench_count));
+        }
+    }
+
+    fn cancel_benchmark(&mut self) {
+        self.benchmark = None;
+        let message = "Benchmark cancelled".to_string();
+        log::info!("{message}");
+        self.status_message = Some(message);
+    }
+
+    fn finish_benchmark(&mut self) {
+        let Some(benchmark) = self.benchmark.take() else {
+            return;
+        };
+        let summary = benchmark.finish();
+        log::info!("Benchmark: {summary}");
+
+        let message = match &self.cli.bench_output {
+            Some(path) => match append_benchmark(path, &summary) {
+                Ok(()) => format!("{summary} (appended to {})", path.display()),
+                Err(e) => format!("{summary} (failed to write {}: {e})", path.display()),
+            },
+            None => summary,
+        };
+        self.status_message = Some(message);
+    }
+
+    fn export_report(&mut self) {
+        let message = match self.write_report() {
+            Ok(path) => format!("Report saved to {}", path.display()),
+            Err(e) => format!("Failed to save report: {e}"),
+        };
+        log::info!("{message}");
+        self.status_message = Some(message);
+    }
+
+    fn save_screenshot(&mut self, ansi: bool) {
+        let message = match self.write_screenshot(ansi) {
+            Ok(path) => format!("Screenshot saved to {}", path.display()),
+            Err(e) => format!("Failed to save screenshot: {e}"),
+        };
+        log::info!("{message}");
+        self.status_message = Some(message);
+    }
+
+    // Render the UI exactly as on screen into an offscreen buffer, and save that as text
+    fn write_screenshot(&self, ansi: bool) -> Result<PathBuf> {
+        let (width, height) = crossterm::terminal::size()?;
+        let area = Rect::new(0, 0, width, height);
+        let mut buf = Buffer::empty(area);
+        self.render(area, &mut buf);
+
+        let extension = if ansi { "ans" } else { "txt" };
+        let path = PathBuf::from(format!(
+            "ec_screenshot_{}.{extension}",
+            chrono::Local::now().format("%Y%m%d_%H%M%S")
+        ));
+        fs::write(&path, screenshot::buffer_to_string(&buf, ansi))?;
+        Ok(path)
+    }
+
+    fn copy_value(&mut self) {
+        let value = self
+            .modules
+            .get(&self.selected_tab)
+            .expect("Tab must exist")
+            .copy_value();
+        let message = match value {
+            Some(value) => match self.set_clipboard(&value) {
+                Ok(()) => format!("Copied \"{value}\""),
+                Err(e) => format!("Clipboard unavailable: {e}"),
+            },
+            None => "Nothing to copy on this tab".to_string(),
+        };
+        log::info!("{message}");
+        self.status_message = Some(message);
+    }
+
+    // Headless systems have no clipboard, so this is expected to fail there
+    fn set_clipboard(&mut self, value: &str) -> Result<(), arboard::Error> {
+        let clipboard = match &mut self.clipboard {
+            Some(clipboard) => clipboard,
+            None => self.clipboard.insert(arboard::Clipboard::new()?),
+        };
+        clipboard.set_text(value)
+    }
+
+    // Snapshot every module's latest data into a Markdown file in the working directory
+    fn write_report(&self) -> Result<PathBuf> {
+        let now = chrono::Local::now();
+        let firmware_version = match &self.firmware_version {
+            Ok(version) => version.clone(),
+            Err(e) => format!("unknown ({e})"),
+        };
+
+        let mut report = format!(
+            "# ODP EC Report\n\nGenerated: {}\n\nFirmware version: {firmware_version}\n\n",
+            now.format("%Y-%m-%d %H:%M:%S")
+        );
+        for module in self.modules.values() {
+            report.push_str(&format!("## {}\n\n", module.title()));
+            report.push_str(&module.report());
+        }
+
+        let path = PathBuf::from(format!("ec_report_{}.md", now.format("%Y%m%d_%H%M%S")));
+        fs::write(&path, report)?;
+        Ok(path)
     }
 
     fn quit(&mut self) {
+        log::info!("Quitting");
         self.state = AppState::Quitting;
     }
 
     fn render_tabs(&self, area: Rect, buf: &mut Buffer) {
-        let titles = SelectedTab::iter().map(SelectedTab::title);
+        let titles = self.modules.keys().copied().map(SelectedTab::title);
         let highlight_style = (Color::default(), self.selected_tab.palette().c700);
-        let selected_tab_index = self.selected_tab as usize;
+        let selected_tab_index = self.modules.keys().position(|tab| *tab == self.selected_tab);
         Tabs::new(titles)
             .highlight_style(highlight_style)
             .select(selected_tab_index)
@@ -177,21 +708,138 @@ impl<S: Source + Clone + 'static> App<S> {
             .render(area, buf);
     }
 
+    // Warn about a slow EC, both while we're blocked on it and after the fact
+    fn ec_badge(&self) -> Option<String> {
+        if self.waiting {
+            Some(" WAITING FOR EC… ".to_string())
+        } else if self.last_update >= SLOW_UPDATE {
+            Some(format!(" SLOW EC {} ms ", self.last_update.as_millis()))
+        } else {
+            None
+        }
+    }
+
+    fn render_firmware_version(&self, area: Rect, buf: &mut Buffer) {
+        let version = match &self.firmware_version {
+            Ok(version) => format!("FW {version}"),
+            Err(_) => "FW unknown".to_string(),
+        };
+        Line::raw(version).right_aligned().render(area, buf);
+    }
+
     fn render_selected_tab(&self, area: Rect, buf: &mut Buffer) {
         let module = self.modules.get(&self.selected_tab).expect("Tab must exist");
-        let block = self.selected_tab.block().title(module.title());
+        let mut block = self.selected_tab.block().title(module.title());
+
+        // Reads are shared between tabs, so list every paused one rather than guess which belong to this tab
+        let paused = self.source.paused_reads();
+        if !paused.is_empty() {
+            let paused: Vec<String> = paused
+                .iter()
+                .map(|(operation, retry_in)| format!("{operation} {} s", retry_in.as_secs() + 1))
+                .collect();
+            block = block.title_bottom(
+                Line::from(format!(
+                    " Paused after repeated errors: {} | F5 retries now ",
+                    paused.join(", ")
+                ))
+                .fg(tailwind::RED.c400),
+            );
+        }
         let inner = block.inner(area);
 
         block.render(area, buf);
         module.render(inner, buf);
     }
+
+    // The current tab's values as decoded from the EC, before any labels or units are applied
+    fn render_raw_values(&self, area: Rect, buf: &mut Buffer) {
+        let [area] = Layout::horizontal([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let values = self
+            .modules
+            .get(&self.selected_tab)
+            .map(|module| module.raw_values())
+            .unwrap_or_default();
+        let lines: Vec<Line<'_>> = if values.is_empty() {
+            vec![Line::raw("No raw values for this tab").italic()]
+        } else {
+            values
+                .into_iter()
+                .map(|(label, value)| Line::from(vec![format!("{label}: ").bold(), value.into()]))
+                .collect()
+        };
+
+        Clear.render(area, buf);
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(
+                Block::bordered()
+                    .title(format!(" Raw values: {} | D or Esc to close ", self.selected_tab))
+                    .border_style(tailwind::AMBER.c500),
+            )
+            .render(area, buf);
+    }
+
+    fn render_transactions(&self, area: Rect, buf: &mut Buffer) {
+        let [area] = Layout::horizontal([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        // Newest first, so the calls that just happened are always visible
+        let lines: Vec<Line<'_>> = self
+            .source
+            .transactions()
+            .iter()
+            .rev()
+            .map(|transaction| {
+                let line = format!(
+                    "{}  {:>8.1} ms  {}  {}",
+                    common::format_wall_clock(transaction.at),
+                    transaction.duration.as_secs_f64() * 1000.0,
+                    transaction.operation,
+                    transaction.error.as_deref().unwrap_or("OK")
+                );
+                if transaction.error.is_some() {
+                    Line::from(line.fg(tailwind::RED.c400))
+                } else {
+                    Line::raw(line)
+                }
+            })
+            .collect();
+
+        Clear.render(area, buf);
+        Paragraph::new(lines)
+            .block(
+                Block::bordered()
+                    .title(" Recent EC transactions | T or Esc to close ")
+                    .border_style(tailwind::AMBER.c500),
+            )
+            .render(area, buf);
+    }
 }
 
-impl<S: Source + 'static> Widget for &App<S> {
+impl<S: Source + Send + 'static> Widget for &App<S> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         use Constraint::{Length, Min};
-        let vertical = Layout::vertical([Length(1), Min(0), Length(1)]);
-        let [header_area, inner_area, footer_area] = vertical.areas(area);
+
+        if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+            render_too_small(area, buf);
+            return;
+        }
+
+        // The status message gets a line of its own above the footer, rather than covering the key hints
+        let status_height = u16::from(self.status_message.is_some());
+        let vertical = Layout::vertical([Length(1), Min(0), Length(status_height), Length(1)]);
+        let [header_area, inner_area, status_area, footer_area] = vertical.areas(area);
 
         let horizontal = Layout::horizontal([Min(0), Length(20)]);
         let [tabs_area, title_area] = horizontal.areas(header_area);
@@ -199,29 +847,46 @@ impl<S: Source + 'static> Widget for &App<S> {
         render_title(title_area, buf);
         self.render_tabs(tabs_area, buf);
         self.render_selected_tab(inner_area, buf);
-        render_footer(footer_area, buf);
+        if self.show_raw {
+            self.render_raw_values(inner_area, buf);
+        }
+        if self.show_transactions {
+            self.render_transactions(inner_area, buf);
+        }
+        if let Some(benchmark) = &self.benchmark {
+            render_benchmark(benchmark, inner_area, buf);
+        }
+        if let Some(palette) = &self.palette {
+            palette.render(inner_area, buf);
+        }
+        render_footer(
+            footer_area,
+            buf,
+            self.frozen,
+            self.source.is_read_only(),
+            self.ec_badge(),
+        );
+        self.render_firmware_version(footer_area, buf);
+        if let Some(message) = &self.status_message {
+            Line::raw(message.as_str()).render(status_area, buf);
+        }
+
+        // Done as a last pass so no widget needs to know about it
+        if self.cli.no_color {
+            common::strip_colors(buf);
+        }
     }
 }
 
 impl<S: Source> Drop for App<S> {
     fn drop(&mut self) {
         ratatui::restore();
-    }
-}
-
-impl SelectedTab {
-    /// Get the previous tab, if there is no previous tab return the current tab.
-    fn previous(self) -> Self {
-        let current_index: usize = self as usize;
-        let previous_index = current_index.saturating_sub(1);
-        Self::from_repr(previous_index).unwrap_or(self)
-    }
 
-    /// Get the next tab, if there is no next tab return the current tab.
-    fn next(self) -> Self {
-        let current_index = self as usize;
-        let next_index = current_index.saturating_add(1);
-        Self::from_repr(next_index).unwrap_or(self)
+        self.saved_state.set(SAVED_TAB_KEY, self.selected_tab);
+        self.saved_state.set(SAVED_TWELVE_HOUR_KEY, common::twelve_hour());
+        if let Err(e) = self.saved_state.save() {
+            eprintln!("Failed to save UI state: {e}");
+        }
     }
 }
 
@@ -229,12 +894,60 @@ fn render_title(area: Rect, buf: &mut Buffer) {
     "ODP EC Demo App".bold().render(area, buf);
 }
 
-fn render_footer(area: Rect, buf: &mut Buffer) {
-    Line::raw("◄ ► to change tab | Press q to quit")
+fn render_too_small(area: Rect, buf: &mut Buffer) {
+    let size = format!("{}x{}, need {MIN_WIDTH}x{MIN_HEIGHT}", area.width, area.height);
+    let [area] = Layout::vertical([Constraint::Length(2)]).flex(Flex::Center).areas(area);
+    Paragraph::new(vec![Line::from("Terminal too small".bold()), Line::raw(size)])
         .centered()
         .render(area, buf);
 }
 
+// Timestamped, so the file can track results across firmware versions
+fn append_benchmark(path: &std::path::Path, summary: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{} {summary}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"))
+}
+
+fn render_benchmark(benchmark: &Benchmark, area: Rect, buf: &mut Buffer) {
+    let [area] = Layout::horizontal([Constraint::Percentage(60)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Length(3)]).flex(Flex::Center).areas(area);
+
+    let (done, total) = benchmark.progress();
+    Clear.render(area, buf);
+    Gauge::default()
+        .block(
+            Block::bordered()
+                .title(" Benchmarking GET_TMP | Esc to cancel ")
+                .border_style(tailwind::AMBER.c500),
+        )
+        .gauge_style(tailwind::AMBER.c500)
+        .ratio(done as f64 / total.max(1) as f64)
+        .label(format!("{done} / {total}"))
+        .render(area, buf);
+}
+
+fn render_footer(area: Rect, buf: &mut Buffer, frozen: bool, read_only: bool, ec_badge: Option<String>) {
+    let mut line = Line::default();
+    if let Some(badge) = ec_badge {
+        line.push_span(badge.bold().fg(tailwind::SLATE.c950).bg(tailwind::ROSE.c300));
+        line.push_span(" ");
+    }
+    if read_only {
+        line.push_span(" READ-ONLY ".bold().fg(tailwind::SLATE.c950).bg(tailwind::AMBER.c300));
+        line.push_span(" ");
+    }
+    if frozen {
+        line.push_span(" FROZEN ".bold().fg(tailwind::SLATE.c950).bg(tailwind::SKY.c300));
+        line.push_span(" ");
+    }
+    let hints: Vec<&str> = BINDINGS.iter().filter_map(|binding| binding.hint).collect();
+    line.push_span(hints.join(" | "));
+    line.centered().render(area, buf);
+}
+
 impl SelectedTab {
     /// Return tab's name as a styled `Line`
     fn title(self) -> Line<'static> {
@@ -254,10 +967,14 @@ impl SelectedTab {
 
     const fn palette(self) -> tailwind::Palette {
         match self {
+            Self::TabOverview => tailwind::SKY,
             Self::TabBattery => tailwind::BLUE,
             Self::TabThermal => tailwind::EMERALD,
             Self::TabRTC => tailwind::INDIGO,
             Self::TabUCSI => tailwind::RED,
+            Self::TabExplorer => tailwind::AMBER,
+            Self::TabEvents => tailwind::VIOLET,
+            Self::TabCompare => tailwind::TEAL,
         }
     }
 }
@@ -2,6 +2,7 @@ use crate::Source;
 use crate::app::Module;
 use crate::common;
 use crate::widgets::battery;
+use crate::widgets::validated_input::ValidatedInput;
 use color_eyre::{Report, Result, eyre::eyre};
 
 use ratatui::style::Modifier;
@@ -10,43 +11,111 @@ use ratatui::widgets::{Row, StatefulWidget, Table, Widget};
 use ratatui::{
     buffer::Buffer,
     crossterm::event::{Event, KeyCode, KeyEventKind},
-    layout::{Constraint, Direction, Rect},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style, Stylize, palette::tailwind},
     text::{Line, Span},
     widgets::{Block, Paragraph},
 };
-use tui_input::{Input, backend::crossterm::EventHandler};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 const BATGAUGE_COLOR_HIGH: Color = tailwind::GREEN.c500;
 const BATGAUGE_COLOR_MEDIUM: Color = tailwind::YELLOW.c500;
 const BATGAUGE_COLOR_LOW: Color = tailwind::RED.c500;
+const TIMELINE_COLOR_CHARGING: Color = tailwind::GREEN.c700;
+const TIMELINE_COLOR_DISCHARGING: Color = tailwind::RED.c700;
+const STATE_COLOR_CRITICAL: Color = tailwind::RED.c500;
+const BTP_COLOR_MISMATCH: Color = tailwind::YELLOW.c500;
+const SPARKLINE_COLOR_VOLTAGE: Color = tailwind::CYAN.c500;
+const SPARKLINE_COLOR_RATE: Color = tailwind::FUCHSIA.c500;
 const LABEL_COLOR: Color = tailwind::SLATE.c200;
+const CYCLE_COLOR: Color = tailwind::SKY.c400;
+const MAX_ALERTS: usize = 20;
 const MAX_SAMPLES: usize = 60;
+// Capacity samples kept for scrolling back through the graph
+const HISTORY_SAMPLES: usize = 3600;
+const CYCLE_DEFAULT_STEPS: u32 = 10;
+// Longest sweep accepted, far more than is useful and small enough that a typo can't exhaust memory
+const CYCLE_MAX_STEPS: u32 = 1000;
+// Well above what a laptop charger delivers, so anything larger is a typo
+const CHARGE_LIMIT_MAX_MA: u32 = 10_000;
+// What _BIX reports for a capacity the battery doesn't know
+const UNKNOWN_CAPACITY: u32 = 0xFFFF_FFFF;
 
+/// Battery state bitfield as reported by _BST
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
-pub enum ChargeState {
-    #[default]
-    Charging,
-    Discharging,
-}
+pub struct ChargeState(u32);
 
-impl TryFrom<u32> for ChargeState {
-    type Error = Report;
-    fn try_from(value: u32) -> Result<Self> {
-        match value {
-            1 => Ok(Self::Discharging),
-            2 => Ok(Self::Charging),
-            _ => Err(eyre!("Unknown charging state")),
-        }
+impl ChargeState {
+    const DISCHARGING: u32 = 1 << 0;
+    const CHARGING: u32 = 1 << 1;
+    const CRITICAL: u32 = 1 << 2;
+    const CHARGE_LIMITING: u32 = 1 << 3;
+    const ALL: u32 = Self::DISCHARGING | Self::CHARGING | Self::CRITICAL | Self::CHARGE_LIMITING;
+
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
     }
-}
 
-impl ChargeState {
-    fn as_str(&self) -> &'static str {
-        match self {
-            Self::Charging => "Charging",
-            Self::Discharging => "Discharging",
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+
+    pub const fn discharging(&self) -> bool {
+        self.0 & Self::DISCHARGING != 0
+    }
+
+    pub const fn charging(&self) -> bool {
+        self.0 & Self::CHARGING != 0
+    }
+
+    pub const fn critical(&self) -> bool {
+        self.0 & Self::CRITICAL != 0
+    }
+
+    pub const fn charge_limiting(&self) -> bool {
+        self.0 & Self::CHARGE_LIMITING != 0
+    }
+
+    // Undefined bits set, or charging and discharging at the same time which ACPI disallows
+    const fn is_unknown(&self) -> bool {
+        self.0 & !Self::ALL != 0 || (self.charging() && self.discharging())
+    }
+
+    // Decode the state into styled spans, highlighting the critical flag
+    pub(crate) fn as_spans(&self) -> Vec<Span<'static>> {
+        if self.is_unknown() {
+            return vec![Span::raw(format!("Unknown ({:#04x})", self.0))];
+        }
+
+        let mut flags = vec![];
+        if self.charging() {
+            flags.push(Span::raw("Charging"));
+        } else if self.discharging() {
+            flags.push(Span::raw("Discharging"));
+        } else {
+            flags.push(Span::raw("Idle"));
+        }
+        if self.charge_limiting() {
+            flags.push(Span::raw("Charge Limiting"));
+        }
+        if self.critical() {
+            flags.push(Span::styled(
+                "CRITICAL",
+                Style::default().fg(STATE_COLOR_CRITICAL).bold(),
+            ));
         }
+
+        let mut spans = Vec::with_capacity(flags.len() * 2);
+        for (i, flag) in flags.into_iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw(" | "));
+            }
+            spans.push(flag);
+        }
+        spans
     }
 }
 
@@ -141,8 +210,47 @@ impl SwapCap {
     }
 }
 
+/// Power source as reported by _PSR
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PowerSource {
+    Ac,
+    Battery,
+}
+
+impl TryFrom<u32> for PowerSource {
+    type Error = Report;
+    fn try_from(value: u32) -> Result<Self> {
+        match value {
+            0 => Ok(Self::Battery),
+            1 => Ok(Self::Ac),
+            _ => Err(eyre!("Unknown power source")),
+        }
+    }
+}
+
+impl PowerSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ac => "AC Power",
+            Self::Battery => "On Battery",
+        }
+    }
+
+    /// Label led by an icon, which is left out when emoji are disabled
+    pub fn as_str_with_icon(&self) -> String {
+        if common::ascii_status() {
+            return self.as_str().to_string();
+        }
+        let icon = match self {
+            Self::Ac => "🔌",
+            Self::Battery => "🔋",
+        };
+        format!("{icon} {}", self.as_str())
+    }
+}
+
 /// BST: ACPI Battery Status
-#[derive(Default)]
+#[derive(Debug, Default, Clone)]
 pub struct BstData {
     pub state: ChargeState,
     pub rate: u32,
@@ -151,7 +259,7 @@ pub struct BstData {
 }
 
 /// BIX: ACPI Battery Information eXtended
-#[derive(Default)]
+#[derive(Debug, Default, Clone)]
 pub struct BixData {
     pub revision: u32,
     pub power_unit: PowerUnit, // 0 - mW, 1 - mA
@@ -176,24 +284,255 @@ pub struct BixData {
     pub swap_cap: SwapCap,
 }
 
+/// BIF: ACPI Battery Information, the legacy subset of BIX implemented by older ECs
+#[derive(Debug, Default, Clone)]
+pub struct BifData {
+    pub power_unit: PowerUnit,
+    pub design_capacity: u32,
+    pub last_full_capacity: u32,
+    pub battery_technology: BatteryTechnology,
+    pub design_voltage: u32,
+    pub warning_capacity: u32,
+    pub low_capacity: u32,
+    pub capacity_gran1: u32,
+    pub capacity_gran2: u32,
+    pub model_number: String,
+    pub serial_number: String,
+    pub battery_type: String,
+    pub oem_info: String,
+}
+
+/// Battery details beyond BIX, each only reported by some batteries, used for warranty and aging analysis
+#[derive(Debug, Default, Clone)]
+pub struct BatteryExtended {
+    pub manufacture_date: Option<chrono::NaiveDate>,
+    /// Serial number with the extra detail that doesn't fit BIX's, e.g. a cell lot code
+    pub serial_extended: Option<String>,
+    /// Full capacity at each discharge rate, as (hours, capacity) so (5, 9700) is the capacity at C/5
+    pub rate_capacities: Vec<(u32, u32)>,
+}
+
+impl From<BifData> for BixData {
+    // Fields only BIX has are left at their defaults
+    fn from(bif: BifData) -> Self {
+        Self {
+            power_unit: bif.power_unit,
+            design_capacity: bif.design_capacity,
+            last_full_capacity: bif.last_full_capacity,
+            battery_technology: bif.battery_technology,
+            design_voltage: bif.design_voltage,
+            warning_capacity: bif.warning_capacity,
+            low_capacity: bif.low_capacity,
+            capacity_gran1: bif.capacity_gran1,
+            capacity_gran2: bif.capacity_gran2,
+            model_number: bif.model_number,
+            serial_number: bif.serial_number,
+            battery_type: bif.battery_type,
+            oem_info: bif.oem_info,
+            ..Default::default()
+        }
+    }
+}
+
+/// State of charge as a percentage of the last full charge capacity, `None` if that is unknown (zero)
+///
+/// Last full charge rather than design capacity is the denominator, so a worn battery still reads 100% when
+/// full, matching what the OS reports to users.
+pub fn state_of_charge(remaining_capacity: u32, last_full_capacity: u32) -> Option<f64> {
+    (last_full_capacity > 0).then(|| (remaining_capacity as f64 * 100.0 / last_full_capacity as f64).min(100.0))
+}
+
+/// A capacity graph sample along with the charge state it was taken in
+#[derive(Default, Clone, Copy)]
+struct CapacitySample {
+    capacity: u32,
+    state: ChargeState,
+}
+
+impl From<CapacitySample> for f64 {
+    fn from(sample: CapacitySample) -> Self {
+        sample.capacity as f64
+    }
+}
+
+/// Automated trippoint sweep, stepping once per update and recording BST readings to CSV
+struct CycleTest {
+    values: Vec<u32>,
+    step: usize,
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl CycleTest {
+    fn start(values: Vec<u32>) -> Result<Self> {
+        let path = PathBuf::from(format!(
+            "battery_cycle_{}.csv",
+            chrono::Local::now().format("%Y%m%d_%H%M%S")
+        ));
+        let mut writer = BufWriter::new(File::create(&path)?);
+        writeln!(writer, "time,btp_set,btp_read,state,rate,capacity,voltage")?;
+
+        Ok(Self {
+            values,
+            step: 0,
+            path,
+            writer,
+        })
+    }
+
+    fn current(&self) -> u32 {
+        self.values[self.step]
+    }
+
+    fn record(&mut self, btp_read: Option<u32>, bst: Option<&BstData>) -> Result<()> {
+        let time = chrono::Local::now().format(common::EXPORT_TIME_FORMAT);
+        let btp_read = btp_read.map(|btp| btp.to_string()).unwrap_or_default();
+        let bst = bst
+            .map(|bst| format!("{:#x},{},{},{}", bst.state.bits(), bst.rate, bst.capacity, bst.voltage))
+            .unwrap_or_else(|| ",,,".to_string());
+        writeln!(self.writer, "{time},{},{btp_read},{bst}", self.current())?;
+        Ok(())
+    }
+}
+
+fn validate_charge_limit(input: &str) -> Result<u32, String> {
+    let limit = common::validate_numeric(input)?;
+    if limit <= CHARGE_LIMIT_MAX_MA {
+        Ok(limit)
+    } else {
+        Err(format!("Expected at most {CHARGE_LIMIT_MAX_MA} mA"))
+    }
+}
+
+/// Snap a trippoint to the capacity granularity the firmware works in
+///
+/// Per ACPI, granularity 1 applies below the warning capacity and granularity 2 from there up, so values above
+/// warning are stepped from the warning capacity. A granularity of zero is treated as unknown and leaves the
+/// value alone.
+pub fn snap_trippoint(trippoint: u32, bix: &BixData) -> u32 {
+    let snap = |value: u32, step: u32| {
+        value
+            .saturating_add(step / 2)
+            .checked_div(step)
+            .map_or(value, |steps| steps * step)
+    };
+
+    if trippoint < bix.warning_capacity {
+        snap(trippoint, bix.capacity_gran1).min(bix.warning_capacity)
+    } else {
+        let above = trippoint - bix.warning_capacity;
+        // Rounding up can run past u32::MAX for huge inputs, in which case snap down instead. It can only overflow
+        // when it rounded, so the granularity isn't zero.
+        bix.warning_capacity
+            .checked_add(snap(above, bix.capacity_gran2))
+            .unwrap_or_else(|| bix.warning_capacity + above - above % bix.capacity_gran2)
+    }
+}
+
+// Parse a sweep as `start..end:step`, an empty input sweeps from the warning capacity to last full capacity
+fn parse_cycle_range(input: &str, bix: &BixData) -> Result<Vec<u32>, String> {
+    let invalid = || format!("Invalid cycle range '{input}', expected start..end:step");
+    let (start, end, step) = if input.trim().is_empty() {
+        let (start, end) = (bix.warning_capacity, bix.last_full_capacity);
+        (start, end, (end.saturating_sub(start) / CYCLE_DEFAULT_STEPS).max(1))
+    } else {
+        let (range, step) = input.split_once(':').ok_or_else(invalid)?;
+        let (start, end) = range.split_once("..").ok_or_else(invalid)?;
+        (
+            common::parse_numeric(start).ok_or_else(invalid)?,
+            common::parse_numeric(end).ok_or_else(invalid)?,
+            common::parse_numeric(step).ok_or_else(invalid)?,
+        )
+    };
+
+    if step == 0 || end < start {
+        return Err(invalid());
+    }
+    // Checked before collecting, since a range like 0..0xFFFFFFFF:1 would otherwise allocate gigabytes. In u64 as the
+    // count of a full u32 range doesn't fit in one.
+    let steps = u64::from((end - start) / step) + 1;
+    if steps > u64::from(CYCLE_MAX_STEPS) {
+        return Err(format!("Cycle range has {steps} steps, at most {CYCLE_MAX_STEPS}"));
+    }
+    Ok((start..=end).step_by(step as usize).collect())
+}
+
 struct BatteryState {
     btp: u32,
-    btp_input: Input,
+    btp_set: Option<u32>,
+    // Value typed in by the user when it had to be snapped to the BIX granularity
+    btp_requested: Option<u32>,
+    btp_input: ValidatedInput<u32>,
+    // Charger current limit in mA, None if it could not be read
+    charge_limit: Option<u32>,
+    charge_limit_echo: Option<common::Echo<u32>>,
+    charge_limit_success: bool,
+    charge_limit_input: ValidatedInput<u32>,
+    focus: common::Focus,
     bst_success: bool,
+    bst_freshness: common::Freshness,
     bix_success: bool,
+    // Battery info came from the legacy _BIF, so BIX-only fields are unknown
+    bif_fallback: bool,
+    // Whether the extended battery info section of the info table is expanded
+    show_extended: bool,
     btp_success: bool,
-    samples: common::SampleBuf<u32, MAX_SAMPLES>,
+    btp_read_success: bool,
+    show_timeline: bool,
+    wall_clock: bool,
+    show_sparklines: bool,
+    samples: common::SampleBuf<CapacitySample, HISTORY_SAMPLES>,
+    viewport: common::Viewport,
+    voltage_samples: common::SampleBuf<u32, MAX_SAMPLES>,
+    rate_samples: common::SampleBuf<u32, MAX_SAMPLES>,
+    cycle_test: Option<CycleTest>,
+    cycle_status: Option<String>,
+    // Outcome of the last capacity export
+    export_status: Option<String>,
+    // Whether capacity was below the BIX warning/low levels at the last reading, so alarms fire on the crossing only
+    below_warning: bool,
+    below_low: bool,
+    // Timestamped capacity alarms, most recent last
+    alerts: Vec<String>,
+    // Charge state bits and trippoint, highlighted briefly when they change
+    state_changed: common::Changed<u32>,
+    btp_changed: common::Changed<u32>,
 }
 
 impl Default for BatteryState {
     fn default() -> Self {
         Self {
             btp: 0,
-            btp_input: Input::default(),
+            btp_set: None,
+            btp_requested: None,
+            btp_input: ValidatedInput::new(common::validate_numeric),
+            charge_limit: None,
+            charge_limit_echo: None,
+            charge_limit_success: true,
+            charge_limit_input: ValidatedInput::new(validate_charge_limit),
+            focus: common::Focus::default(),
             bst_success: false,
+            bst_freshness: common::Freshness::default(),
             bix_success: false,
+            bif_fallback: false,
+            show_extended: false,
             btp_success: true,
+            btp_read_success: false,
+            show_timeline: true,
+            wall_clock: false,
+            show_sparklines: true,
             samples: common::SampleBuf::default(),
+            viewport: common::Viewport::default(),
+            voltage_samples: common::SampleBuf::default(),
+            rate_samples: common::SampleBuf::default(),
+            cycle_test: None,
+            cycle_status: None,
+            export_status: None,
+            below_warning: false,
+            below_low: false,
+            alerts: Vec::new(),
+            state_changed: Default::default(),
+            btp_changed: Default::default(),
         }
     }
 }
@@ -202,9 +541,20 @@ impl Default for BatteryState {
 pub struct Battery<S: Source> {
     bst_data: BstData,
     bix_data: BixData,
+    // None if _PSR could not be read
+    power_source: Option<PowerSource>,
+    // Read along with BIX, None if the battery doesn't report any
+    extended: Option<BatteryExtended>,
     state: BatteryState,
-    t_sec: usize,
-    t_min: usize,
+    // When the last capacity sample was due, None until the first
+    last_graph_sample: Option<Instant>,
+    // Capacity samples taken so far, for the relative time axis
+    t_graph: usize,
+    // How often a capacity sample is taken
+    graph_interval: Duration,
+    // Gauge color thresholds in % of design capacity (warning, low), instead of the BIX capacities
+    gauge_thresholds: Option<(u32, u32)>,
+    bell: bool,
     source: S,
 }
 
@@ -214,211 +564,659 @@ impl<S: Source> Module for Battery<S> {
     }
 
     fn update(&mut self) {
+        // BIX is static so one good read is enough, but the first can fail if the EC is still starting up
+        if !self.state.bix_success {
+            self.update_bix();
+        }
+
         if let Ok(bst_data) = self.source.get_bst() {
             self.bst_data = bst_data;
+            self.state.voltage_samples.insert(self.bst_data.voltage);
+            self.state.rate_samples.insert(self.bst_data.rate);
             self.state.bst_success = true;
+            self.state.bst_freshness.mark();
+            self.state.state_changed.update(self.bst_data.state.bits());
+            self.check_alarms();
         } else {
             self.state.bst_success = false;
         }
 
-        // In mock demo, update graph every second, but real-life update every minute
-        #[cfg(feature = "mock")]
-        let update_graph = true;
-        #[cfg(not(feature = "mock"))]
-        let update_graph = (self.t_sec % 60) == 0;
+        self.power_source = self.source.get_power_source().ok();
+        self.update_btp();
+        self.state.charge_limit = self.source.get_charge_current_limit().ok();
+        self.step_cycle_test();
 
-        self.t_sec += 1;
+        // Capacity changes slowly, so only graph every graph_interval of wall time, whatever the tick rate
+        let now = Instant::now();
+        let update_graph = match self.last_graph_sample {
+            None => true,
+            Some(last) => now.duration_since(last) >= self.graph_interval,
+        };
         if update_graph {
-            self.state.samples.insert(self.bst_data.capacity);
-            self.t_min += 1;
+            // Step by whole intervals so the cadence doesn't drift with tick jitter, unless we fell behind
+            self.last_graph_sample = match self.last_graph_sample {
+                Some(last) if now.duration_since(last) < self.graph_interval * 2 => Some(last + self.graph_interval),
+                _ => Some(now),
+            };
+            self.state.samples.insert(CapacitySample {
+                capacity: self.bst_data.capacity,
+                state: self.bst_data.state,
+            });
+            self.t_graph += 1;
         }
     }
 
     fn render(&self, area: Rect, buf: &mut Buffer) {
         let [info_area, charge_area] = common::area_split(area, Direction::Horizontal, 80, 20);
         self.render_info(info_area, buf);
-        self.render_battery(charge_area, buf);
+
+        if self.state.show_sparklines {
+            let [battery_area, voltage_area, rate_area] = Layout::vertical([
+                Constraint::Percentage(60),
+                Constraint::Percentage(20),
+                Constraint::Percentage(20),
+            ])
+            .areas(charge_area);
+            self.render_battery(battery_area, buf);
+            self.render_sparklines(voltage_area, rate_area, buf);
+        } else {
+            self.render_battery(charge_area, buf);
+        }
     }
 
     fn handle_event(&mut self, evt: &Event) {
+        // The trippoint and charge limit inputs can't be edited in read-only mode
+        let inputs = if self.source.is_read_only() { 0 } else { 2 };
         if let Event::Key(key) = evt
+            && key.kind == KeyEventKind::Press
+            && self.state.focus.handle_key(key.code, inputs)
+        {
+            // Focus moved
+        } else if let Event::Key(key) = evt
             && key.code == KeyCode::Enter
             && key.kind == KeyEventKind::Press
+            && self.state.focus.is(0)
         {
-            if let Ok(btp) = self.state.btp_input.value_and_reset().parse() {
+            if let Some(requested) = self.state.btp_input.submit() {
+                // Granularity is only known once BIX has been read
+                let btp = if self.state.bix_success {
+                    snap_trippoint(requested, &self.bix_data)
+                } else {
+                    requested
+                };
                 if self.source.set_btp(btp).is_ok() {
-                    self.state.btp = btp;
+                    self.state.btp_set = Some(btp);
+                    self.state.btp_requested = (btp != requested).then_some(requested);
                     self.state.btp_success = true;
                 } else {
                     self.state.btp_success = false;
                 }
+                self.update_btp();
             }
-        } else {
-            let _ = self.state.btp_input.handle_event(evt);
+        } else if let Event::Key(key) = evt
+            && key.code == KeyCode::Enter
+            && key.kind == KeyEventKind::Press
+            && self.state.focus.is(1)
+        {
+            if let Some(limit) = self.state.charge_limit_input.submit() {
+                if let Err(e) = self.source.set_charge_current_limit(limit) {
+                    log::error!("Failed to set charge current limit: {e}");
+                    self.state.charge_limit_success = false;
+                } else {
+                    let read = self.source.get_charge_current_limit();
+                    self.state.charge_limit = read.as_ref().ok().copied();
+                    self.state.charge_limit_echo = Some(common::Echo::check(limit, read));
+                    self.state.charge_limit_success = true;
+                }
+            }
+        } else if self.state.focus.is(0) {
+            self.state.btp_input.handle_event(evt);
+        } else if self.state.focus.is(1) {
+            self.state.charge_limit_input.handle_event(evt);
+        } else if let Event::Key(key) = evt
+            && key.code == KeyCode::Char('t')
+            && key.kind == KeyEventKind::Press
+        {
+            self.state.show_timeline = !self.state.show_timeline;
+        } else if let Event::Key(key) = evt
+            && key.code == KeyCode::Char('w')
+            && key.kind == KeyEventKind::Press
+        {
+            self.state.wall_clock = !self.state.wall_clock;
+        } else if let Event::Key(key) = evt
+            && key.code == KeyCode::Char('g')
+            && key.kind == KeyEventKind::Press
+        {
+            self.state.show_sparklines = !self.state.show_sparklines;
+        } else if let Event::Key(key) = evt
+            && key.code == KeyCode::Char('k')
+            && key.kind == KeyEventKind::Press
+        {
+            self.toggle_cycle_test();
+        } else if let Event::Key(key) = evt
+            && key.code == KeyCode::Char('x')
+            && key.kind == KeyEventKind::Press
+        {
+            self.state.show_extended = !self.state.show_extended;
+        } else if let Event::Key(key) = evt
+            && key.code == KeyCode::Char('e')
+            && key.kind == KeyEventKind::Press
+        {
+            self.export_capacity();
+        } else if let Event::Key(key) = evt
+            && key.kind == KeyEventKind::Press
+            && self.state.viewport.handle_key(key.code, self.state.samples.len())
+        {
+            // Viewport already updated
         }
     }
+
+    fn has_focus(&self) -> bool {
+        self.state.focus.any()
+    }
+
+    fn commands(&self) -> Vec<(&'static str, KeyCode)> {
+        vec![
+            ("Show / hide timeline", KeyCode::Char('t')),
+            ("Toggle wall clock axis", KeyCode::Char('w')),
+            ("Show / hide sparklines", KeyCode::Char('g')),
+            ("Start / stop BTP cycle test", KeyCode::Char('k')),
+            ("Expand / collapse extended battery info", KeyCode::Char('x')),
+            ("Export capacity samples to CSV", KeyCode::Char('e')),
+        ]
+    }
+
+    fn raw_values(&self) -> Vec<(String, String)> {
+        let read = |success: bool, value: String| if success { value } else { "Not read".to_string() };
+        vec![
+            (
+                "BST".to_string(),
+                read(self.state.bst_success, format!("{:?}", self.bst_data)),
+            ),
+            (
+                if self.state.bif_fallback { "BIF (as BIX)" } else { "BIX" }.to_string(),
+                read(self.state.bix_success, format!("{:?}", self.bix_data)),
+            ),
+            ("PSR".to_string(), format!("{:?}", self.power_source)),
+            ("Extended".to_string(), format!("{:?}", self.extended)),
+            (
+                "BTP".to_string(),
+                read(self.state.btp_read_success, self.state.btp.to_string()),
+            ),
+            (
+                "Charge current limit".to_string(),
+                format!("{:?}", self.state.charge_limit),
+            ),
+        ]
+    }
+
+    fn copy_value(&self) -> Option<String> {
+        self.state.bst_success.then(|| {
+            format!(
+                "{} {}",
+                self.bst_data.capacity,
+                self.bix_data.power_unit.as_capacity_str()
+            )
+        })
+    }
+
+    fn report(&self) -> String {
+        let info = self
+            .bix_fields()
+            .into_iter()
+            .map(|(label, value)| format!("{label:<24}{value}"));
+        [
+            common::report_section("Battery Status", self.state.bst_success, self.create_status()),
+            common::report_section("Battery Info", self.state.bix_success, info),
+            common::report_section(
+                "Extended Battery Info",
+                self.extended.is_some(),
+                self.extended_fields()
+                    .into_iter()
+                    .map(|(label, value)| format!("{label:<24}{value}")),
+            ),
+            common::report_section("Trippoint", self.state.btp_read_success, self.create_trippoint()),
+            common::report_section(
+                "Charge Current Limit",
+                self.state.charge_limit.is_some(),
+                self.create_charge_limit(),
+            ),
+            common::report_section("Capacity Alarms", true, &self.state.alerts),
+        ]
+        .concat()
+    }
 }
 
 impl<S: Source> Battery<S> {
-    pub fn new(source: S) -> Self {
+    pub fn new(source: S, bell: bool, graph_interval: Duration) -> Self {
         let mut inst = Self {
             bst_data: Default::default(),
             bix_data: Default::default(),
-            state: Default::default(),
-            t_sec: Default::default(),
-            t_min: Default::default(),
+            power_source: None,
+            extended: None,
+            state: BatteryState {
+                samples: common::SampleBuf::with_interval(graph_interval),
+                ..Default::default()
+            },
+            last_graph_sample: None,
+            t_graph: Default::default(),
+            graph_interval,
+            gauge_thresholds: None,
+            bell,
             source,
         };
 
-        // This shouldn't change because BIX info is static so just read once
-        if let Ok(bix_data) = inst.source.get_bix() {
-            inst.bix_data = bix_data;
-            inst.state.bix_success = true;
+        inst.update();
+        inst
+    }
+
+    /// Color the gauge by `(warning, low)` percentages of design capacity instead of the BIX capacities, if given
+    pub fn with_gauge_thresholds(mut self, thresholds: Option<(u32, u32)>) -> Self {
+        self.gauge_thresholds = thresholds;
+        self
+    }
+
+    // Raise an alarm when discharging takes capacity below the BIX warning or low level, like the ACPI battery alarm
+    fn check_alarms(&mut self) {
+        if !self.state.bix_success {
+            return;
+        }
+
+        let capacity = self.bst_data.capacity;
+        let discharging = self.bst_data.state.discharging();
+        let levels = [
+            ("warning", self.bix_data.warning_capacity, &mut self.state.below_warning),
+            ("low", self.bix_data.low_capacity, &mut self.state.below_low),
+        ];
+        let mut alerts = Vec::new();
+        for (name, level, below) in levels {
+            let now_below = capacity < level;
+            if now_below && !*below && discharging {
+                alerts.push(format!(
+                    "{} Capacity {capacity} {} dropped below {name} level {level}",
+                    common::format_wall_clock(std::time::SystemTime::now()),
+                    self.bix_data.power_unit.as_capacity_str()
+                ));
+            }
+            *below = now_below;
+        }
+
+        for alert in alerts {
+            log::warn!("Battery alarm: {alert}");
+            if self.bell {
+                common::ring_bell();
+            }
+            self.state.alerts.push(alert);
+        }
+        if self.state.alerts.len() > MAX_ALERTS {
+            let excess = self.state.alerts.len() - MAX_ALERTS;
+            self.state.alerts.drain(..excess);
+        }
+    }
+
+    // Older ECs only implement _BIF, so fall back to that when _BIX fails
+    fn update_bix(&mut self) {
+        if let Ok(bix_data) = self.source.get_bix() {
+            self.bix_data = bix_data;
+            self.state.bix_success = true;
+            self.state.bif_fallback = false;
+        } else if let Ok(bif_data) = self.source.get_bif() {
+            self.bix_data = bif_data.into();
+            self.state.bix_success = true;
+            self.state.bif_fallback = true;
         } else {
-            inst.state.bix_success = false;
+            self.state.bix_success = false;
         }
 
-        inst.update();
-        inst
+        // Most batteries have none of it, so only ask once rather than polling something that will keep failing
+        if self.state.bix_success {
+            self.extended = self.source.get_battery_extended().ok();
+        }
+    }
+
+    fn toggle_cycle_test(&mut self) {
+        if self.state.cycle_test.is_some() {
+            self.finish_cycle_test("Cycle test stopped");
+            return;
+        }
+
+        // Only clear the input once it's valid, so a typo can be corrected rather than retyped
+        let values = match parse_cycle_range(self.state.btp_input.value(), &self.bix_data) {
+            Ok(values) => values,
+            Err(error) => {
+                self.state.btp_input.set_error(error);
+                return;
+            }
+        };
+        self.state.btp_input.reset();
+
+        match CycleTest::start(values) {
+            Ok(test) => {
+                let btp = test.current();
+                self.state.cycle_test = Some(test);
+                self.set_cycle_btp(btp);
+            }
+            Err(e) => self.state.cycle_status = Some(format!("Failed to start cycle test: {e}")),
+        }
+    }
+
+    fn set_cycle_btp(&mut self, btp: u32) {
+        if let Err(e) = self.source.set_btp(btp) {
+            self.finish_cycle_test(&format!("Cycle test aborted: {e}"));
+        } else {
+            self.state.btp_set = Some(btp);
+            // Cycle values are set as given, so an earlier snap no longer applies
+            self.state.btp_requested = None;
+            self.state.btp_success = true;
+        }
+    }
+
+    // Record the readings for the current step then move on to the next trippoint
+    fn step_cycle_test(&mut self) {
+        let btp_read = self.state.btp_read_success.then_some(self.state.btp);
+        let bst = self.state.bst_success.then_some(&self.bst_data);
+        let Some(test) = self.state.cycle_test.as_mut() else {
+            return;
+        };
+
+        if let Err(e) = test.record(btp_read, bst) {
+            self.finish_cycle_test(&format!("Cycle test aborted: {e}"));
+            return;
+        }
+
+        test.step += 1;
+        if test.step == test.values.len() {
+            self.finish_cycle_test("Cycle test complete");
+        } else {
+            let btp = test.current();
+            self.set_cycle_btp(btp);
+        }
+    }
+
+    fn finish_cycle_test(&mut self, reason: &str) {
+        if let Some(mut test) = self.state.cycle_test.take() {
+            let saved = match test.writer.flush() {
+                Ok(()) => format!("saved to {}", test.path.display()),
+                Err(e) => format!("failed to save {}: {e}", test.path.display()),
+            };
+            self.state.cycle_status = Some(format!("{reason}, {saved}"));
+        }
+    }
+
+    // Write the capacity graph samples to a timestamped CSV in the working directory
+    fn export_capacity(&mut self) {
+        let path = PathBuf::from(format!(
+            "battery_capacity_{}.csv",
+            chrono::Local::now().format("%Y%m%d_%H%M%S")
+        ));
+        let unit = self.bix_data.power_unit.as_capacity_str();
+        let comments = if self.state.bix_success {
+            vec![
+                format!("Capacity in {unit}"),
+                format!("Design capacity: {} {unit}", self.bix_data.design_capacity),
+                format!("Last full capacity: {} {unit}", self.bix_data.last_full_capacity),
+            ]
+        } else {
+            vec!["Battery info not read, capacity unit and design capacity unknown".to_string()]
+        };
+
+        let status = match common::export_samples_csv(&path, &comments, "capacity", &self.state.samples) {
+            Ok(()) => format!("Exported {} samples to {}", self.state.samples.len(), path.display()),
+            Err(e) => format!("Capacity export failed: {e}"),
+        };
+        log::info!("{status}");
+        self.state.export_status = Some(status);
+    }
+
+    // Read back the trippoint so we display what the EC actually uses
+    fn update_btp(&mut self) {
+        if let Ok(btp) = self.source.get_btp() {
+            self.state.btp = btp;
+            self.state.btp_read_success = true;
+            self.state.btp_changed.update(btp);
+        } else {
+            self.state.btp_read_success = false;
+        }
     }
 
     fn render_info(&self, area: Rect, buf: &mut Buffer) {
         let [bix_area, status_area] = common::area_split(area, Direction::Horizontal, 50, 50);
-        let [bst_area, btp_area] = common::area_split(status_area, Direction::Vertical, 70, 30);
+        let [bst_area, setting_area] = common::area_split(status_area, Direction::Vertical, 70, 30);
+        let [btp_area, charge_limit_area] = common::area_split(setting_area, Direction::Horizontal, 60, 40);
         let [bst_chart_area, bst_info_area] = common::area_split(bst_area, Direction::Vertical, 65, 35);
 
         self.render_bix(bix_area, buf);
         self.render_bst(bst_info_area, buf);
-        self.render_bst_chart(bst_chart_area, buf);
         self.render_btp(btp_area, buf);
+        self.render_charge_limit(charge_limit_area, buf);
+
+        if self.state.show_timeline {
+            let [timeline_area, bst_chart_area] =
+                Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(bst_chart_area);
+            self.render_timeline(timeline_area, buf);
+            self.render_bst_chart(bst_chart_area, buf);
+        } else {
+            self.render_bst_chart(bst_chart_area, buf);
+        }
+    }
+
+    // Colors each sample's time slot by the charge state the sample was taken in
+    fn render_timeline(&self, area: Rect, buf: &mut Buffer) {
+        let samples: Vec<&CapacitySample> = self.state.samples.window_iter(&self.state.viewport).collect();
+        for x in 0..area.width {
+            let idx = x as usize * self.state.viewport.width() / area.width.max(1) as usize;
+            let Some(sample) = samples.get(idx) else {
+                break;
+            };
+            let color = if sample.state.charging() {
+                TIMELINE_COLOR_CHARGING
+            } else if sample.state.discharging() {
+                TIMELINE_COLOR_DISCHARGING
+            } else {
+                continue;
+            };
+            if let Some(cell) = buf.cell_mut((area.x + x, area.y)) {
+                cell.set_bg(color);
+            }
+        }
     }
 
     fn render_bst_chart(&self, area: Rect, buf: &mut Buffer) {
+        // Without a design capacity (BIX not read yet) scale to the samples instead of collapsing the axis
+        let y_max = if self.bix_data.design_capacity > 0 {
+            self.bix_data.design_capacity
+        } else {
+            self.state.samples.iter().map(|s| s.capacity).max().unwrap_or(0).max(1)
+        };
         let y_labels = [
             "0".bold(),
-            Span::styled(
-                format!("{}", self.bix_data.design_capacity / 2),
-                Style::default().bold(),
-            ),
-            Span::styled(format!("{}", self.bix_data.design_capacity), Style::default().bold()),
+            Span::styled(format!("{}", y_max / 2), Style::default().bold()),
+            Span::styled(format!("{y_max}"), Style::default().bold()),
         ];
+        let (x_axis, x_labels) = if self.state.wall_clock {
+            (
+                "Time".to_string(),
+                self.state.samples.wall_clock_labels(&self.state.viewport),
+            )
+        } else {
+            let x_axis = match self.graph_interval.as_secs() {
+                60 => "Time (m)".to_string(),
+                1 => "Time (s)".to_string(),
+                secs => format!("Time (×{secs} s)"),
+            };
+            (x_axis, common::time_labels(self.t_graph, &self.state.viewport))
+        };
         let graph = common::Graph {
-            title: "Capacity vs Time".to_string(),
+            title: "Capacity vs Time".to_string() + &self.state.viewport.suffix(),
             color: Color::Red,
-            samples: self.state.samples.get(),
-            x_axis: "Time (m)".to_string(),
-            x_bounds: [0.0, 60.0],
-            x_labels: common::time_labels(self.t_min, MAX_SAMPLES),
+            samples: self.state.samples.window(&self.state.viewport),
+            x_axis,
+            x_bounds: self.state.viewport.x_bounds(),
+            x_labels,
             y_axis: format!("Capacity ({})", self.bix_data.power_unit.as_capacity_str()),
-            y_bounds: [0.0, self.bix_data.design_capacity as f64],
+            y_bounds: [0.0, y_max as f64],
             y_labels,
+            markers: Vec::new(),
+            series: Vec::new(),
         };
         common::render_chart(area, buf, graph);
     }
 
-    fn create_info(&self) -> Vec<Row<'static>> {
+    // Label and value pairs for the static BIX data
+    fn bix_fields(&self) -> Vec<(&'static str, String)> {
         let power_unit = self.bix_data.power_unit;
+        let bix_only = |value: String| {
+            if self.state.bif_fallback {
+                "— (not in _BIF)".to_string()
+            } else {
+                value
+            }
+        };
 
         vec![
-            Row::new(vec![
-                Text::styled("Revision", Style::default().add_modifier(Modifier::BOLD)),
-                format!("{}", self.bix_data.revision).into(),
-            ]),
-            Row::new(vec![
-                Text::raw("Power Unit").add_modifier(Modifier::BOLD),
-                format!("{}", self.bix_data.power_unit.as_rate_str()).into(),
-            ]),
-            Row::new(vec![
-                Text::raw("Design Capacity").add_modifier(Modifier::BOLD),
-                format!("{} {}", self.bix_data.design_capacity, power_unit.as_capacity_str()).into(),
-            ]),
-            Row::new(vec![
-                Text::raw("Last Full Capacity").add_modifier(Modifier::BOLD),
-                format!("{} {}", self.bix_data.last_full_capacity, power_unit.as_capacity_str()).into(),
-            ]),
-            Row::new(vec![
-                Text::raw("Battery Technology").add_modifier(Modifier::BOLD),
-                format!("{}", self.bix_data.battery_technology.as_str()).into(),
-            ]),
-            Row::new(vec![
-                Text::raw("Design Voltage").add_modifier(Modifier::BOLD),
-                format!("{} mV", self.bix_data.design_voltage).into(),
-            ]),
-            Row::new(vec![
-                Text::raw("Warning Capacity").add_modifier(Modifier::BOLD),
-                format!("{} {}", self.bix_data.warning_capacity, power_unit.as_capacity_str()).into(),
-            ]),
-            Row::new(vec![
-                Text::raw("Low Capacity").add_modifier(Modifier::BOLD),
-                format!("{} {}", self.bix_data.low_capacity, power_unit.as_capacity_str()).into(),
-            ]),
-            Row::new(vec![
-                Text::raw("Cycle Count").add_modifier(Modifier::BOLD),
-                format!("{}", self.bix_data.cycle_count).into(),
-            ]),
-            Row::new(vec![
-                Text::raw("Accuracy").add_modifier(Modifier::BOLD),
-                format!("{}%", self.bix_data.accuracy as f64 / 1000.0).into(),
-            ]),
-            Row::new(vec![
-                Text::raw("Max Sample Time").add_modifier(Modifier::BOLD),
-                format!("{} ms", self.bix_data.max_sample_time).into(),
-            ]),
-            Row::new(vec![
-                Text::raw("Mix Sample Time").add_modifier(Modifier::BOLD),
-                format!("{} ms", self.bix_data.min_sample_time).into(),
-            ]),
-            Row::new(vec![
-                Text::raw("Max Average Interval").add_modifier(Modifier::BOLD),
-                format!("{} ms", self.bix_data.max_average_interval).into(),
-            ]),
-            Row::new(vec![
-                Text::raw("Min Average Interval").add_modifier(Modifier::BOLD),
-                format!("{} ms", self.bix_data.min_average_interval).into(),
-            ]),
-            Row::new(vec![
-                Text::raw("Capacity Granularity 1").add_modifier(Modifier::BOLD),
-                format!("{} {}", self.bix_data.capacity_gran1, power_unit.as_capacity_str()).into(),
-            ]),
-            Row::new(vec![
-                Text::raw("Capacity Granularity 2").add_modifier(Modifier::BOLD),
-                format!("{} {}", self.bix_data.capacity_gran2, power_unit.as_capacity_str()).into(),
-            ]),
-            Row::new(vec![
-                Text::raw("Model Number").add_modifier(Modifier::BOLD),
-                format!("{}", self.bix_data.model_number).into(),
-            ]),
-            Row::new(vec![
-                Text::raw("Serial Number").add_modifier(Modifier::BOLD),
-                format!("{}", self.bix_data.serial_number).into(),
-            ]),
-            Row::new(vec![
-                Text::raw("Battery Type").add_modifier(Modifier::BOLD),
-                format!("{}", self.bix_data.battery_type).into(),
-            ]),
-            Row::new(vec![
-                Text::raw("OEM Info").add_modifier(Modifier::BOLD),
-                format!("{}", self.bix_data.oem_info).into(),
-            ]),
-            Row::new(vec![
-                Text::raw("Swapping Capability").add_modifier(Modifier::BOLD),
-                format!("{}", self.bix_data.swap_cap.as_str()).into(),
-            ]),
+            ("Revision", bix_only(format!("{}", self.bix_data.revision))),
+            ("Power Unit", format!("{}", self.bix_data.power_unit.as_rate_str())),
+            (
+                "Design Capacity",
+                format!("{} {}", self.bix_data.design_capacity, power_unit.as_capacity_str()),
+            ),
+            (
+                "Last Full Capacity",
+                format!("{} {}", self.bix_data.last_full_capacity, power_unit.as_capacity_str()),
+            ),
+            (
+                "Battery Technology",
+                format!("{}", self.bix_data.battery_technology.as_str()),
+            ),
+            ("Design Voltage", format!("{} mV", self.bix_data.design_voltage)),
+            (
+                "Warning Capacity",
+                format!("{} {}", self.bix_data.warning_capacity, power_unit.as_capacity_str()),
+            ),
+            (
+                "Low Capacity",
+                format!("{} {}", self.bix_data.low_capacity, power_unit.as_capacity_str()),
+            ),
+            ("Cycle Count", bix_only(format!("{}", self.bix_data.cycle_count))),
+            (
+                "Accuracy",
+                bix_only(format!("{}%", self.bix_data.accuracy as f64 / 1000.0)),
+            ),
+            (
+                "Max Sample Time",
+                bix_only(format!("{} ms", self.bix_data.max_sample_time)),
+            ),
+            (
+                "Mix Sample Time",
+                bix_only(format!("{} ms", self.bix_data.min_sample_time)),
+            ),
+            (
+                "Max Average Interval",
+                bix_only(format!("{} ms", self.bix_data.max_average_interval)),
+            ),
+            (
+                "Min Average Interval",
+                bix_only(format!("{} ms", self.bix_data.min_average_interval)),
+            ),
+            (
+                "Capacity Granularity 1",
+                format!("{} {}", self.bix_data.capacity_gran1, power_unit.as_capacity_str()),
+            ),
+            (
+                "Capacity Granularity 2",
+                format!("{} {}", self.bix_data.capacity_gran2, power_unit.as_capacity_str()),
+            ),
+            ("Model Number", format!("{}", self.bix_data.model_number)),
+            ("Serial Number", format!("{}", self.bix_data.serial_number)),
+            ("Battery Type", format!("{}", self.bix_data.battery_type)),
+            ("OEM Info", format!("{}", self.bix_data.oem_info)),
+            (
+                "Swapping Capability",
+                bix_only(format!("{}", self.bix_data.swap_cap.as_str())),
+            ),
         ]
     }
 
+    // Label and value pairs for whichever extended fields the battery reports
+    fn extended_fields(&self) -> Vec<(String, String)> {
+        let Some(extended) = &self.extended else {
+            return Vec::new();
+        };
+        let capacity_str = self.bix_data.power_unit.as_capacity_str();
+
+        let mut fields = Vec::new();
+        if let Some(date) = extended.manufacture_date {
+            fields.push(("Manufacture Date".to_string(), date.format("%Y-%m-%d").to_string()));
+        }
+        if let Some(serial) = &extended.serial_extended {
+            fields.push(("Extended Serial".to_string(), serial.clone()));
+        }
+        for (hours, capacity) in &extended.rate_capacities {
+            let rate = if *hours == 1 {
+                "1C".to_string()
+            } else {
+                format!("C/{hours}")
+            };
+            fields.push((format!("Capacity @ {rate}"), format!("{capacity} {capacity_str}")));
+        }
+        fields
+    }
+
+    fn create_info(&self) -> Vec<Row<'static>> {
+        // All-zero defaults would look like real data, so show nothing until BIX has been read
+        if !self.state.bix_success {
+            return vec![Row::new(vec![Text::raw("Waiting for BIX...").italic()])];
+        }
+
+        let row =
+            |label: String, value: String| Row::new(vec![Text::raw(label).add_modifier(Modifier::BOLD), value.into()]);
+        let mut rows: Vec<Row<'static>> = self
+            .bix_fields()
+            .into_iter()
+            .map(|(label, value)| row(label.to_string(), value))
+            .collect();
+
+        // Collapsed by default, since it pushes the table past the height of most terminals
+        if self.extended.is_some() {
+            if self.state.show_extended {
+                rows.push(row("▼ Extended".to_string(), "x to collapse".to_string()));
+                rows.extend(
+                    self.extended_fields()
+                        .into_iter()
+                        .map(|(label, value)| row(label, value)),
+                );
+            } else {
+                rows.push(row("▶ Extended".to_string(), "x to expand".to_string()));
+            }
+        }
+        rows
+    }
+
     fn render_bix(&self, area: Rect, buf: &mut Buffer) {
         let widths = [Constraint::Percentage(30), Constraint::Percentage(70)];
         let table = Table::new(self.create_info(), widths)
-            .block(Block::bordered().title("Battery Info"))
+            .block(Block::bordered().title(common::title_str_with_status(
+                if self.state.bif_fallback {
+                    "Battery Info (_BIF)"
+                } else {
+                    "Battery Info"
+                },
+                self.state.bix_success,
+            )))
             .style(Style::new().white());
         Widget::render(table, area, buf);
     }
 
     fn create_status(&self) -> Vec<Line<'static>> {
         let power_unit = self.bix_data.power_unit;
-        vec![
-            Line::raw(format!("State:               {}", self.bst_data.state.as_str())),
+        let mut lines = vec![
+            Line::raw(format!(
+                "Power Source:        {}",
+                self.power_source
+                    .map_or_else(|| "Unknown".to_string(), |source| source.as_str_with_icon())
+            )),
+            Line::from([vec![Span::raw("State:               ")], self.bst_data.state.as_spans()].concat())
+                .style(self.state.state_changed.style()),
             Line::raw(format!(
                 "Present Rate:        {} {}",
                 self.bst_data.rate,
@@ -430,57 +1228,239 @@ impl<S: Source> Battery<S> {
                 power_unit.as_capacity_str()
             )),
             Line::raw(format!("Present Voltage:     {} mV", self.bst_data.voltage)),
-        ]
+            Line::raw(format!(
+                "State of Charge:     {}",
+                state_of_charge(self.bst_data.capacity, self.bix_data.last_full_capacity)
+                    .map_or_else(|| "Unknown".to_string(), |soc| format!("{soc:.0}%"))
+            )),
+        ];
+        if let Some(alert) = self.state.alerts.last() {
+            lines.push(Line::styled(
+                format!("Last alarm: {alert}"),
+                Style::default().fg(STATE_COLOR_CRITICAL),
+            ));
+        }
+        lines
     }
 
     fn render_bst(&self, area: Rect, buf: &mut Buffer) {
-        let title = common::title_str_with_status("Battery Status", self.state.bst_success);
+        let freshness = self.state.bst_freshness;
+        let title = common::title_str_with_status("Battery Status", self.state.bst_success) + &freshness.suffix();
         let title = common::title_block(&title, 0, LABEL_COLOR);
-        Paragraph::new(self.create_status()).block(title).render(area, buf);
+        let mut lines = self.create_status();
+        lines.extend(
+            self.state
+                .export_status
+                .as_ref()
+                .map(|status| Line::styled(status.clone(), Style::default().fg(CYCLE_COLOR))),
+        );
+        Paragraph::new(lines)
+            .style(freshness.style())
+            .block(title)
+            .render(area, buf);
     }
 
     fn create_trippoint(&self) -> Vec<Line<'static>> {
-        vec![Line::raw(format!(
-            "Current: {} {}",
-            self.state.btp,
-            self.bix_data.power_unit.as_capacity_str()
-        ))]
+        let capacity_str = self.bix_data.power_unit.as_capacity_str();
+        let mut line =
+            Line::raw(format!("Current: {} {}", self.state.btp, capacity_str)).style(self.state.btp_changed.style());
+
+        // Flag an EC that clamped or ignored the last trippoint we set
+        if let Some(btp_set) = self.state.btp_set
+            && self.state.btp_read_success
+            && let Some(mismatch) =
+                common::Echo::check(btp_set, Ok(self.state.btp)).mismatch_str(&format!(" {capacity_str}"))
+        {
+            line.push_span(Span::styled(
+                format!(" (mismatch: {mismatch})"),
+                Style::default().fg(BTP_COLOR_MISMATCH),
+            ));
+        }
+
+        let cycle_line = match (&self.state.cycle_test, &self.state.cycle_status) {
+            (Some(test), _) => Some(format!(
+                "Cycle test: step {}/{} at {} {capacity_str} <k> to stop",
+                test.step + 1,
+                test.values.len(),
+                test.current()
+            )),
+            (None, Some(status)) => Some(status.clone()),
+            (None, None) => None,
+        };
+        let mut lines = vec![line];
+        if let (Some(requested), Some(btp_set)) = (self.state.btp_requested, self.state.btp_set) {
+            lines.push(Line::raw(format!(
+                "Requested {requested} {capacity_str}, snapped to {btp_set} {capacity_str} by BIX granularity"
+            )));
+        }
+        lines.extend(cycle_line.map(|cycle_line| Line::styled(cycle_line, Style::default().fg(CYCLE_COLOR))));
+        lines
     }
 
     fn render_btp(&self, area: Rect, buf: &mut Buffer) {
-        let title_str = common::title_str_with_status("Trippoint", self.state.btp_success);
+        let title_str =
+            common::title_str_with_status("Trippoint", self.state.btp_success && self.state.btp_read_success);
         let title = common::title_block(&title_str, 0, LABEL_COLOR);
         let inner = title.inner(area);
         title.render(area, buf);
 
-        let [current_area, input_area] = common::area_split(inner, Direction::Vertical, 30, 70);
+        let [current_area, input_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(inner);
 
         Paragraph::new(self.create_trippoint()).render(current_area, buf);
         self.render_btp_input(input_area, buf);
     }
 
     fn render_btp_input(&self, area: Rect, buf: &mut Buffer) {
-        let width = area.width.max(3) - 3;
-        let scroll = self.state.btp_input.visual_scroll(width as usize);
+        self.state.btp_input.render(
+            "Set Trippoint <ENTER> | Cycle test start..end:step <k>",
+            self.source.is_read_only(),
+            self.state.focus.is(0),
+            area,
+            buf,
+        );
+    }
 
-        let input = Paragraph::new(self.state.btp_input.value())
-            .style(Style::default())
-            .scroll((0, scroll as u16))
-            .block(Block::bordered().title("Set Trippoint <ENTER>"));
-        input.render(area, buf);
+    fn create_charge_limit(&self) -> Vec<Line<'static>> {
+        let current = match self.state.charge_limit {
+            Some(limit) => format!("{limit} mA"),
+            None => "—".to_string(),
+        };
+        let mut lines = vec![Line::raw(format!("Current: {current}"))];
+        if let Some(mismatch) = self.state.charge_limit_echo.and_then(|echo| echo.mismatch_str(" mA")) {
+            lines.push(Line::styled(
+                format!("Mismatch: {mismatch}"),
+                Style::default().fg(BTP_COLOR_MISMATCH),
+            ));
+        }
+        lines
+    }
+
+    fn render_charge_limit(&self, area: Rect, buf: &mut Buffer) {
+        let title_str = common::title_str_with_status(
+            "Charge Limit",
+            self.state.charge_limit_success && self.state.charge_limit.is_some(),
+        );
+        let title = common::title_block(&title_str, 0, LABEL_COLOR);
+        let inner = title.inner(area);
+        title.render(area, buf);
+
+        let [current_area, input_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(inner);
+
+        Paragraph::new(self.create_charge_limit()).render(current_area, buf);
+        self.state.charge_limit_input.render(
+            "Set Limit mA <ENTER>",
+            self.source.is_read_only(),
+            self.state.focus.is(1),
+            input_area,
+            buf,
+        );
+    }
+
+    fn render_sparklines(&self, voltage_area: Rect, rate_area: Rect, buf: &mut Buffer) {
+        common::render_sparkline(
+            voltage_area,
+            buf,
+            format!("Voltage {} mV", self.bst_data.voltage),
+            SPARKLINE_COLOR_VOLTAGE,
+            &self.state.voltage_samples,
+        );
+        common::render_sparkline(
+            rate_area,
+            buf,
+            format!("Rate {} {}", self.bst_data.rate, self.bix_data.power_unit.as_rate_str()),
+            SPARKLINE_COLOR_RATE,
+            &self.state.rate_samples,
+        );
     }
 
     fn render_battery(&self, area: Rect, buf: &mut Buffer) {
-        let mut state =
-            battery::BatteryState::new(self.bst_data.capacity, self.bst_data.state == ChargeState::Charging);
+        let mut state = battery::BatteryState::new(self.bst_data.capacity, self.bst_data.state.charging());
+
+        // Only the gauge color changes, the info table and alarms keep using the BIX values
+        // A percentage of an unknown design capacity means nothing, so the BIX values are kept then too
+        let design_capacity = self.bix_data.design_capacity;
+        let percent_of_design = |percent: u32| (design_capacity as u64 * percent as u64 / 100) as u32;
+        let (warning_capacity, low_capacity) = match self.gauge_thresholds {
+            Some((warning, low)) if design_capacity != UNKNOWN_CAPACITY => {
+                (percent_of_design(warning), percent_of_design(low))
+            }
+            _ => (self.bix_data.warning_capacity, self.bix_data.low_capacity),
+        };
 
         battery::Battery::default()
             .color_high(BATGAUGE_COLOR_HIGH)
             .color_warning(BATGAUGE_COLOR_MEDIUM)
             .color_low(BATGAUGE_COLOR_LOW)
-            .design_capacity(self.bix_data.design_capacity)
-            .warning_capacity(self.bix_data.warning_capacity)
-            .low_capacity(self.bix_data.low_capacity)
+            .design_capacity(design_capacity)
+            .warning_capacity(warning_capacity)
+            .low_capacity(low_capacity)
             .render(area, buf, &mut state)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bix(gran1: u32, gran2: u32) -> BixData {
+        BixData {
+            warning_capacity: 5000,
+            capacity_gran1: gran1,
+            capacity_gran2: gran2,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn snap_trippoint_below_warning() {
+        let bix = bix(10, 100);
+        assert_eq!(snap_trippoint(1234, &bix), 1230);
+        assert_eq!(snap_trippoint(1235, &bix), 1240);
+        // Rounding up never crosses into the granularity 2 range
+        assert_eq!(snap_trippoint(4999, &bix), 5000);
+    }
+
+    #[test]
+    fn snap_trippoint_at_warning() {
+        assert_eq!(snap_trippoint(5000, &bix(10, 100)), 5000);
+    }
+
+    #[test]
+    fn snap_trippoint_above_warning() {
+        let bix = bix(10, 100);
+        assert_eq!(snap_trippoint(5049, &bix), 5000);
+        assert_eq!(snap_trippoint(5050, &bix), 5100);
+        assert_eq!(snap_trippoint(5234, &bix), 5200);
+        // Rounding up would overflow, so this snaps down
+        assert_eq!(snap_trippoint(u32::MAX, &bix), 4_294_967_200);
+    }
+
+    #[test]
+    fn snap_trippoint_zero_granularity() {
+        let bix = bix(0, 0);
+        assert_eq!(snap_trippoint(1234, &bix), 1234);
+        assert_eq!(snap_trippoint(5234, &bix), 5234);
+    }
+
+    #[test]
+    fn parse_cycle_range_steps() {
+        assert_eq!(
+            parse_cycle_range("100..400:100", &bix(10, 100)),
+            Ok(vec![100, 200, 300, 400])
+        );
+        assert_eq!(
+            parse_cycle_range("0..999:1", &bix(10, 100)).map(|values| values.len()),
+            Ok(1000)
+        );
+        assert!(parse_cycle_range("400..100:100", &bix(10, 100)).is_err());
+        assert!(parse_cycle_range("100..400:0", &bix(10, 100)).is_err());
+    }
+
+    #[test]
+    fn parse_cycle_range_too_many_steps() {
+        assert_eq!(
+            parse_cycle_range("0..0xFFFFFFFF:1", &bix(10, 100)),
+            Err("Cycle range has 4294967296 steps, at most 1000".to_string())
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use crate::Source;
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Back-to-back GET_TMP requests, timing each to measure the throughput and latency of the path to the EC
+///
+/// Requests are issued from a worker thread, so the UI keeps drawing progress and handling keys while a slow EC
+/// blocks. Dropping the benchmark cancels it once the request in flight returns.
+pub struct Benchmark {
+    count: usize,
+    // Shared with the worker, which counts requests as they complete and checks for cancellation between them
+    done: Arc<AtomicUsize>,
+    cancel: Arc<AtomicBool>,
+    worker: Option<JoinHandle<Results>>,
+}
+
+impl Benchmark {
+    /// Start `count` requests on a worker thread
+    pub fn start<S: Source + Send + 'static>(source: S, count: usize) -> Self {
+        let done = Arc::new(AtomicUsize::new(0));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let worker = {
+            let done = done.clone();
+            let cancel = cancel.clone();
+            std::thread::spawn(move || Results::collect(&source, count, &done, &cancel))
+        };
+        Self {
+            count,
+            done,
+            cancel,
+            worker: Some(worker),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.worker.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+
+    /// Requests completed so far, and the total
+    pub fn progress(&self) -> (usize, usize) {
+        (self.done.load(Ordering::Relaxed), self.count)
+    }
+
+    /// Wait for the worker and summarize its results, see [`Benchmark::is_done`]
+    pub fn finish(mut self) -> String {
+        match self.worker.take().map(JoinHandle::join) {
+            Some(Ok(results)) => results.summary(),
+            _ => "Benchmark failed, the worker thread panicked".to_string(),
+        }
+    }
+}
+
+impl Drop for Benchmark {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+// What the worker measured
+struct Results {
+    latencies: Vec<Duration>,
+    errors: usize,
+    elapsed: Duration,
+}
+
+impl Results {
+    fn collect<S: Source>(source: &S, count: usize, done: &AtomicUsize, cancel: &AtomicBool) -> Self {
+        let mut results = Self {
+            latencies: Vec::with_capacity(count),
+            errors: 0,
+            elapsed: Duration::ZERO,
+        };
+        let started = Instant::now();
+        while results.latencies.len() < count && !cancel.load(Ordering::Relaxed) {
+            let request = Instant::now();
+            if source.get_temperature().is_err() {
+                results.errors += 1;
+            }
+            results.latencies.push(request.elapsed());
+            done.store(results.latencies.len(), Ordering::Relaxed);
+        }
+        results.elapsed = started.elapsed();
+        results
+    }
+
+    /// One line summary of the results
+    fn summary(&self) -> String {
+        let mut sorted = self.latencies.clone();
+        sorted.sort();
+
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        // Nearest-rank percentile
+        let percentile = |p: usize| {
+            sorted
+                .get((sorted.len() * p).div_ceil(100).saturating_sub(1))
+                .map_or(0.0, |&latency| ms(latency))
+        };
+        let rate = sorted.len() as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON);
+
+        format!(
+            "{} GET_TMP in {:.3} s, {rate:.1} req/s, p50 {:.2} ms, p90 {:.2} ms, p99 {:.2} ms, max {:.2} ms, {} errors",
+            sorted.len(),
+            self.elapsed.as_secs_f64(),
+            percentile(50),
+            percentile(90),
+            percentile(99),
+            sorted.last().map_or(0.0, |&latency| ms(latency)),
+            self.errors
+        )
+    }
+}
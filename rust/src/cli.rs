@@ -0,0 +1,347 @@
+use clap::{Arg, ArgAction, Command, error::ErrorKind, value_parser};
+use std::{net::IpAddr, path::PathBuf};
+
+/// Command line options
+#[derive(Debug, Clone)]
+pub struct Cli {
+    /// Maximum number of times per second the screen is redrawn
+    pub fps: u32,
+    /// How often every module reads fresh values from the source
+    pub tick: std::time::Duration,
+    /// Disable every command that writes to the EC
+    pub read_only: bool,
+    /// File to write the application log to, logging is disabled if not given
+    pub log_file: Option<PathBuf>,
+    /// Ring the terminal bell on alerts such as thermal trips
+    pub bell: bool,
+    /// How often the battery capacity graph takes a sample
+    pub battery_graph_interval: std::time::Duration,
+    /// How long values that rarely change stay highlighted after changing, zero to disable
+    pub highlight: std::time::Duration,
+    /// Render without colors, also set by the `NO_COLOR` environment variable
+    pub no_color: bool,
+    /// Use ASCII status markers such as `[OK]` instead of emoji
+    pub ascii: bool,
+    /// Temperatures (warning, critical) in °C at which the temperature readout turns yellow and red,
+    /// following the fan Ramping and Max levels if not given
+    pub temp_colors: Option<(f64, f64)>,
+    /// Percentages of design capacity (warning, low) below which the battery gauge turns yellow and red,
+    /// following the BIX warning and low capacities if not given
+    pub battery_gauge: Option<(u32, u32)>,
+    /// When the thermal tab flags the fan as stalled
+    pub fan_stall: crate::thermal::StallDetect,
+    /// Names of the modules (tabs) to show, see [`MODULES`]
+    pub modules: Vec<String>,
+    /// Dump file to replay instead of talking to the EC, see [`crate::dump::Dump`]
+    pub from_dump: Option<PathBuf>,
+    /// Dump file to compare the source against in an extra tab, see [`crate::compare::Compare`]
+    pub compare: Option<PathBuf>,
+    /// Number of requests the benchmark sends
+    pub bench_count: usize,
+    /// File benchmark results are appended to, one line per run
+    pub bench_output: Option<PathBuf>,
+    /// File every reading is recorded to each tick, see [`crate::record::Recorder`]
+    pub record: Option<PathBuf>,
+    /// Port the latest readings are served on as JSON, see [`crate::web::WebServer`]
+    pub web: Option<u16>,
+    /// Address the web server listens on, loopback unless remote access is asked for
+    pub web_bind: IpAddr,
+    /// Names of the disruptive commands that ask before running, see [`CONFIRMABLE`]
+    pub confirm: Vec<String>,
+    /// File overriding the built-in thermal variable GUIDs, see [`crate::common::guid::load_map`]
+    pub guid_map: Option<PathBuf>,
+}
+
+/// GUID map loaded from the working directory when `--guid-map` isn't given
+pub const DEFAULT_GUID_MAP: &str = "guid-map.toml";
+
+// Capacity changes slowly on real hardware, so a sample a minute, but the mock demo should visibly move
+#[cfg(feature = "mock")]
+const DEFAULT_BATTERY_GRAPH_INTERVAL: &str = "1";
+#[cfg(not(feature = "mock"))]
+const DEFAULT_BATTERY_GRAPH_INTERVAL: &str = "60";
+
+/// Names accepted by `--confirm`
+pub const CONFIRMABLE: [&str; 5] = ["soft-reset", "hard-reset", "role-swap", "set-timer", "set-wake-policy"];
+
+/// Names accepted by `--modules`, in tab order
+pub const MODULES: [&str; 7] = ["overview", "battery", "thermal", "rtc", "ucsi", "explorer", "events"];
+
+impl Cli {
+    /// Parse options from the process arguments, exiting with usage on error
+    pub fn parse() -> Self {
+        let matches = Self::command().get_matches();
+
+        let temp_colors: Option<Vec<f64>> = matches
+            .get_many::<f64>("temp-colors")
+            .map(|values| values.copied().collect());
+        let temp_colors = match temp_colors.as_deref() {
+            None => None,
+            Some(&[warning, critical]) if warning < critical => Some((warning, critical)),
+            Some(_) => Self::command()
+                .error(
+                    ErrorKind::InvalidValue,
+                    "--temp-colors takes WARN,CRIT with WARN below CRIT",
+                )
+                .exit(),
+        };
+
+        let fan_stall: Vec<u32> = matches
+            .get_many::<u32>("fan-stall")
+            .expect("has default")
+            .copied()
+            .collect();
+        let fan_stall = match fan_stall[..] {
+            [min_commanded, max_rpm, samples] if min_commanded > max_rpm && samples > 0 => {
+                crate::thermal::StallDetect {
+                    min_commanded: min_commanded.into(),
+                    max_rpm: max_rpm.into(),
+                    samples: samples as usize,
+                }
+            }
+            _ => Self::command()
+                .error(
+                    ErrorKind::InvalidValue,
+                    "--fan-stall takes COMMANDED,TACH,SAMPLES with COMMANDED above TACH and at least 1 sample",
+                )
+                .exit(),
+        };
+
+        let battery_gauge: Option<Vec<u32>> = matches
+            .get_many::<u32>("battery-gauge")
+            .map(|values| values.copied().collect());
+        let battery_gauge = match battery_gauge.as_deref() {
+            None => None,
+            Some(&[warning, low]) if warning > low => Some((warning, low)),
+            Some(_) => Self::command()
+                .error(
+                    ErrorKind::InvalidValue,
+                    "--battery-gauge takes WARN,LOW with WARN above LOW",
+                )
+                .exit(),
+        };
+
+        Self {
+            fps: *matches.get_one::<u32>("fps").expect("has default"),
+            tick: std::time::Duration::from_millis(*matches.get_one::<u64>("tick-ms").expect("has default")),
+            read_only: matches.get_flag("read-only"),
+            log_file: matches.get_one::<PathBuf>("log-file").cloned(),
+            bell: matches.get_flag("bell"),
+            battery_graph_interval: std::time::Duration::from_secs(
+                *matches.get_one::<u64>("battery-graph-interval").expect("has default"),
+            ),
+            highlight: std::time::Duration::from_millis(*matches.get_one::<u64>("highlight-ms").expect("has default")),
+            // https://no-color.org: any non-empty value disables color
+            no_color: matches.get_flag("no-color")
+                || std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()),
+            ascii: matches.get_flag("ascii"),
+            temp_colors,
+            battery_gauge,
+            fan_stall,
+            modules: matches
+                .get_many::<String>("modules")
+                .expect("has default")
+                .cloned()
+                .collect(),
+            from_dump: matches.get_one::<PathBuf>("from-dump").cloned(),
+            compare: matches.get_one::<PathBuf>("compare").cloned(),
+            bench_count: *matches.get_one::<usize>("bench-count").expect("has default"),
+            bench_output: matches.get_one::<PathBuf>("bench-output").cloned(),
+            record: matches.get_one::<PathBuf>("record").cloned(),
+            web: matches.get_one::<u16>("web").copied(),
+            web_bind: *matches.get_one::<IpAddr>("web-bind").expect("has default"),
+            confirm: if matches.get_flag("no-confirm") {
+                Vec::new()
+            } else {
+                matches
+                    .get_many::<String>("confirm")
+                    .expect("has default")
+                    .cloned()
+                    .collect()
+            },
+            // The default file is optional, but one named explicitly must exist
+            guid_map: matches
+                .get_one::<PathBuf>("guid-map")
+                .cloned()
+                .or_else(|| Some(PathBuf::from(DEFAULT_GUID_MAP)).filter(|path| path.is_file())),
+        }
+    }
+
+    fn command() -> Command {
+        Command::new(env!("CARGO_PKG_NAME"))
+            .version(env!("CARGO_PKG_VERSION"))
+            .about(env!("CARGO_PKG_DESCRIPTION"))
+            .arg(
+                Arg::new("fps")
+                    .long("fps")
+                    .value_name("N")
+                    .help("Maximum number of times per second the screen is redrawn")
+                    .default_value("10")
+                    .value_parser(value_parser!(u32).range(1..=120)),
+            )
+            .arg(
+                Arg::new("tick-ms")
+                    .long("tick-ms")
+                    .value_name("MS")
+                    .help("Milliseconds between reads of fresh values from the EC")
+                    .default_value("1000")
+                    .value_parser(value_parser!(u64).range(100..=60_000)),
+            )
+            .arg(
+                Arg::new("read-only")
+                    .long("read-only")
+                    .help("Disable every command that writes to the EC")
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("log-file")
+                    .long("log-file")
+                    .value_name("PATH")
+                    .help("Write a timestamped log of errors, commands and UI state changes to PATH")
+                    .value_parser(value_parser!(PathBuf)),
+            )
+            .arg(
+                Arg::new("bell")
+                    .long("bell")
+                    .help("Ring the terminal bell on alerts such as thermal trips")
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("battery-graph-interval")
+                    .long("battery-graph-interval")
+                    .value_name("SECS")
+                    .help("Seconds between battery capacity graph samples")
+                    .default_value(DEFAULT_BATTERY_GRAPH_INTERVAL)
+                    .value_parser(value_parser!(u64).range(1..)),
+            )
+            .arg(
+                Arg::new("highlight-ms")
+                    .long("highlight-ms")
+                    .value_name("MS")
+                    .help("How long a value such as the charge state or a fan level is highlighted after it changes, 0 to disable")
+                    .default_value("1500")
+                    .value_parser(value_parser!(u64)),
+            )
+            .arg(
+                Arg::new("no-color")
+                    .long("no-color")
+                    .help("Render without colors, relying on symbols and labels for state [also set by NO_COLOR]")
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("ascii")
+                    .long("ascii")
+                    .help("Show status as [OK]/[ERR] rather than emoji, for terminals that misalign them")
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("temp-colors")
+                    .long("temp-colors")
+                    .value_name("WARN,CRIT")
+                    .help("Temperatures in °C at which the readout turns yellow and red [default: fan Ramping and Max levels]")
+                    .value_delimiter(',')
+                    .value_parser(value_parser!(f64)),
+            )
+            .arg(
+                Arg::new("fan-stall")
+                    .long("fan-stall")
+                    .value_name("COMMANDED,TACH,SAMPLES")
+                    .help("Flag the fan as stalled when commanded above COMMANDED RPM but reading below TACH RPM for SAMPLES updates in a row")
+                    .value_delimiter(',')
+                    .default_value("500,100,3")
+                    .value_parser(value_parser!(u32)),
+            )
+            .arg(
+                Arg::new("battery-gauge")
+                    .long("battery-gauge")
+                    .value_name("WARN,LOW")
+                    .help("Charge in % of design capacity below which the battery gauge turns yellow and red [default: BIX warning and low capacities]")
+                    .value_delimiter(',')
+                    .value_parser(value_parser!(u32).range(0..=100)),
+            )
+            .arg(
+                Arg::new("modules")
+                    .long("modules")
+                    .value_name("LIST")
+                    .help("Comma separated list of tabs to show, e.g. battery,thermal,rtc")
+                    .value_delimiter(',')
+                    .default_values(MODULES)
+                    .value_parser(MODULES),
+            )
+            .arg(
+                Arg::new("from-dump")
+                    .long("from-dump")
+                    .value_name("PATH")
+                    .help("Show fixed values captured from a real device in PATH instead of talking to the EC")
+                    .value_parser(value_parser!(PathBuf)),
+            )
+            .arg(
+                Arg::new("compare")
+                    .long("compare")
+                    .value_name("PATH")
+                    .help("Add a tab comparing readings side by side with a dump in PATH, e.g. one captured from a known good firmware")
+                    .value_parser(value_parser!(PathBuf)),
+            )
+            .arg(
+                Arg::new("bench-count")
+                    .long("bench-count")
+                    .value_name("N")
+                    .help("Number of back-to-back GET_TMP requests the benchmark (F6) sends")
+                    .default_value("100")
+                    .value_parser(value_parser!(usize)),
+            )
+            .arg(
+                Arg::new("bench-output")
+                    .long("bench-output")
+                    .value_name("PATH")
+                    .help("Append benchmark results to PATH, to track them across firmware versions")
+                    .value_parser(value_parser!(PathBuf)),
+            )
+            .arg(
+                Arg::new("confirm")
+                    .long("confirm")
+                    .value_name("LIST")
+                    .help("Comma separated list of commands that ask for confirmation before running")
+                    .value_delimiter(',')
+                    .default_values(["hard-reset", "role-swap", "set-timer", "set-wake-policy"])
+                    .value_parser(CONFIRMABLE),
+            )
+            .arg(
+                Arg::new("no-confirm")
+                    .long("no-confirm")
+                    .help("Run every command without asking for confirmation, e.g. for scripted sessions")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with("confirm"),
+            )
+            .arg(
+                Arg::new("record")
+                    .long("record")
+                    .value_name("PATH")
+                    .help("Append every reading to PATH every tick, as NDJSON if PATH ends in .json/.jsonl/.ndjson, otherwise CSV")
+                    .value_parser(value_parser!(PathBuf)),
+            )
+            .arg(
+                Arg::new("web")
+                    .long("web")
+                    .value_name("PORT")
+                    .help("Serve the latest readings as JSON at http://<host>:PORT/snapshot.json, for viewing in a browser")
+                    .value_parser(value_parser!(u16).range(1..)),
+            )
+            .arg(
+                Arg::new("web-bind")
+                    .long("web-bind")
+                    .value_name("ADDRESS")
+                    .help("Address --web listens on, e.g. 0.0.0.0 to let other machines connect")
+                    .default_value("127.0.0.1")
+                    .value_parser(value_parser!(IpAddr))
+                    .requires("web"),
+            )
+            .arg(
+                Arg::new("guid-map")
+                    .long("guid-map")
+                    .value_name("PATH")
+                    .help("Read thermal variable GUIDs from PATH, lines of name = \"GUID\" [default: ./guid-map.toml if present]")
+                    .value_parser(value_parser!(PathBuf)),
+            )
+    }
+}
@@ -1,35 +1,616 @@
 use ratatui::{
     buffer::Buffer,
+    crossterm::event::KeyCode,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style, Stylize},
+    style::{Color, Modifier, Style, Stylize, palette::tailwind},
     symbols,
     text::{Line, Span},
-    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, Padding, Widget},
+    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, Padding, Sparkline, Widget},
 };
 use std::collections::VecDeque;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Well-known thermal variable GUIDs used with GET_VAR/SET_VAR
+///
+/// These are the built-in defaults. Boards using other GUIDs for the same variables can override them with a
+/// GUID map file, see [`guid::load_map`], and code talking to the EC goes through [`guid::resolve`] to pick that up.
+/// Variables with no GUID defined yet have a placeholder instead, which is never sent, so the map must supply one.
+pub mod guid {
+    use color_eyre::{Result, eyre::eyre};
+    use std::{collections::BTreeMap, fs, path::Path, sync::OnceLock};
+
+    pub const SENSOR_CRT_TEMP: uuid::Uuid = uuid::uuid!("218246e7-baf6-45f1-aa13-07e4845256b8");
+    pub const SENSOR_PROCHOT_TEMP: uuid::Uuid = uuid::uuid!("22dc52d2-fd0b-47ab-95b8-26552f9831a5");
+    pub const FAN_ON_TEMP: uuid::Uuid = uuid::uuid!("ba17b567-c368-48d5-bc6f-a312a41583c1");
+    // Not defined by the firmware yet, only used to look up the GUID map
+    pub const FAN_OFF_TEMP: uuid::Uuid = uuid::Uuid::from_u128(1);
+    pub const FAN_RAMP_TEMP: uuid::Uuid = uuid::uuid!("3a62688c-d95b-4d2d-bacc-90d7a5816bcd");
+    pub const FAN_MAX_TEMP: uuid::Uuid = uuid::uuid!("dcb758b1-f0fd-4ec7-b2c0-ef1e2a547b76");
+    pub const FAN_MIN_RPM: uuid::Uuid = uuid::uuid!("db261c77-934b-45e2-9742-256c62badb7a");
+    pub const FAN_MAX_RPM: uuid::Uuid = uuid::uuid!("5cf839df-8be7-42b9-9ac5-3403ca2c8a6a");
+    pub const FAN_CURRENT_RPM: uuid::Uuid = uuid::uuid!("adf95492-0776-4ffc-84f3-b6c8b5269683");
+    // Not defined by the firmware yet, only used to look up the GUID map
+    pub const SENSOR_SAMPLE_PERIOD: uuid::Uuid = uuid::Uuid::from_u128(2);
+
+    // Names used in the GUID map file, with the built-in GUID each one overrides
+    const MAP_NAMES: [(&str, uuid::Uuid); 10] = [
+        ("sensor_crt_temp", SENSOR_CRT_TEMP),
+        ("sensor_prochot_temp", SENSOR_PROCHOT_TEMP),
+        ("fan_on_temp", FAN_ON_TEMP),
+        ("fan_off_temp", FAN_OFF_TEMP),
+        ("fan_ramp_temp", FAN_RAMP_TEMP),
+        ("fan_max_temp", FAN_MAX_TEMP),
+        ("fan_min_rpm", FAN_MIN_RPM),
+        ("fan_max_rpm", FAN_MAX_RPM),
+        ("fan_current_rpm", FAN_CURRENT_RPM),
+        ("sensor_sample_period", SENSOR_SAMPLE_PERIOD),
+    ];
+
+    // Placeholders standing in for variables with no built-in GUID
+    const UNASSIGNED: [uuid::Uuid; 2] = [FAN_OFF_TEMP, SENSOR_SAMPLE_PERIOD];
+
+    // Overrides by built-in GUID, set once at startup
+    static OVERRIDES: OnceLock<BTreeMap<uuid::Uuid, uuid::Uuid>> = OnceLock::new();
+
+    /// Load GUID overrides from a TOML file of `name = "GUID"` lines, e.g. `fan_current_rpm = "…"`
+    ///
+    /// Only this flat subset of TOML is accepted. Names not given keep their built-in GUID.
+    pub fn load_map(path: &Path) -> Result<()> {
+        let contents = fs::read_to_string(path).map_err(|e| eyre!("Failed to read {}: {e}", path.display()))?;
+        let overrides = parse_map(&contents, &path.display().to_string())?;
+
+        for (default, guid) in &overrides {
+            log::info!("Using {guid} for {}", name(default).unwrap_or("unknown variable"));
+        }
+        OVERRIDES.set(overrides).map_err(|_| eyre!("GUID map already loaded"))
+    }
+
+    // Overrides by built-in GUID from the contents of a GUID map, naming it `origin` in errors
+    fn parse_map(contents: &str, origin: &str) -> Result<BTreeMap<uuid::Uuid, uuid::Uuid>> {
+        let mut overrides = BTreeMap::new();
+        for (index, line) in contents.lines().enumerate() {
+            let location = format!("{origin}:{}", index + 1);
+            let line = line.split_once('#').map_or(line, |(line, _comment)| line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| eyre!("{location}: expected name = \"GUID\""))?;
+            let name = name.trim();
+            let value = value.trim().trim_matches('"');
+            let default = MAP_NAMES
+                .iter()
+                .find(|(known, _)| *known == name)
+                .map(|(_, default)| *default)
+                .ok_or_else(|| {
+                    let known: Vec<&str> = MAP_NAMES.iter().map(|(known, _)| *known).collect();
+                    eyre!(
+                        "{location}: unknown variable '{name}', expected one of {}",
+                        known.join(", ")
+                    )
+                })?;
+            let guid = uuid::Uuid::try_parse(value).map_err(|e| eyre!("{location}: invalid GUID '{value}': {e}"))?;
+            overrides.insert(default, guid);
+        }
+        Ok(overrides)
+    }
+
+    /// The GUID to send for a well-known variable, the override if one was loaded or else the built-in `default`
+    ///
+    /// Fails as unsupported for a variable with no built-in GUID that the map doesn't set either.
+    pub fn resolve(default: uuid::Uuid) -> Result<uuid::Uuid> {
+        let guid = OVERRIDES
+            .get()
+            .and_then(|overrides| overrides.get(&default))
+            .copied()
+            .unwrap_or(default);
+        if UNASSIGNED.contains(&guid) {
+            let map_name = MAP_NAMES
+                .iter()
+                .find(|(_, known)| *known == default)
+                .map_or("?", |(map_name, _)| *map_name);
+            return Err(crate::unsupported(format!(
+                "No built-in GUID for {map_name}, set it in the GUID map"
+            )));
+        }
+        Ok(guid)
+    }
+
+    /// Human readable name of a well-known variable, by its built-in or overridden GUID
+    pub fn name(guid: &uuid::Uuid) -> Option<&'static str> {
+        let guid = MAP_NAMES
+            .iter()
+            .map(|(_, default)| *default)
+            .find(|default| resolve(*default).is_ok_and(|resolved| resolved == *guid))
+            .unwrap_or(*guid);
+        match guid {
+            SENSOR_CRT_TEMP => Some("Sensor critical temp"),
+            SENSOR_PROCHOT_TEMP => Some("Sensor prochot temp"),
+            FAN_ON_TEMP => Some("Fan on temp"),
+            FAN_OFF_TEMP => Some("Fan off temp"),
+            FAN_RAMP_TEMP => Some("Fan ramp temp"),
+            FAN_MAX_TEMP => Some("Fan max temp"),
+            FAN_MIN_RPM => Some("Fan min RPM"),
+            FAN_MAX_RPM => Some("Fan max RPM"),
+            FAN_CURRENT_RPM => Some("Fan current RPM"),
+            SENSOR_SAMPLE_PERIOD => Some("Sensor sample period"),
+            _ => None,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn parse_map_overrides() {
+            let overrides = parse_map(
+                "# Board B\nfan_on_temp = \"11111111-2222-3333-4444-555555555555\"\n\nfan_max_rpm=\"aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee\"\n",
+                "map.toml",
+            )
+            .unwrap();
+            assert_eq!(
+                overrides.get(&FAN_ON_TEMP),
+                Some(&uuid::uuid!("11111111-2222-3333-4444-555555555555"))
+            );
+            assert_eq!(
+                overrides.get(&FAN_MAX_RPM),
+                Some(&uuid::uuid!("aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee"))
+            );
+            assert_eq!(overrides.len(), 2);
+        }
+
+        #[test]
+        fn resolve_unassigned() {
+            assert_eq!(resolve(FAN_ON_TEMP).unwrap(), FAN_ON_TEMP);
+            let error = resolve(FAN_OFF_TEMP).unwrap_err();
+            assert!(error.downcast_ref::<crate::Unsupported>().is_some());
+            assert_eq!(
+                error.to_string(),
+                "No built-in GUID for fan_off_temp, set it in the GUID map"
+            );
+            assert_eq!(name(&FAN_OFF_TEMP), Some("Fan off temp"));
+        }
+
+        #[test]
+        fn parse_map_unknown_name() {
+            let error = parse_map("fan_speed = \"11111111-2222-3333-4444-555555555555\"", "map.toml").unwrap_err();
+            assert!(
+                error
+                    .to_string()
+                    .starts_with("map.toml:1: unknown variable 'fan_speed'"),
+                "{error}"
+            );
+        }
+
+        #[test]
+        fn parse_map_bad_guid() {
+            let error = parse_map("fan_on_temp = \"not-a-guid\"", "map.toml").unwrap_err();
+            assert!(
+                error.to_string().starts_with("map.toml:1: invalid GUID 'not-a-guid'"),
+                "{error}"
+            );
+        }
+
+        #[test]
+        fn parse_map_missing_equals() {
+            let error = parse_map("\nfan_on_temp \"11111111-2222-3333-4444-555555555555\"", "map.toml").unwrap_err();
+            assert_eq!(error.to_string(), "map.toml:2: expected name = \"GUID\"");
+        }
+    }
+}
+
+// Values not successfully refreshed for this long are considered stale
+const STALE_AFTER: Duration = Duration::from_secs(5);
+
+// Timestamps written to exported files, fixed and with the offset so they sort and parse the same anywhere
+pub const EXPORT_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3f%:z";
+
+// Number of samples shown by a graph before it is zoomed
+pub const DEFAULT_WINDOW: usize = 60;
+const MIN_WINDOW: usize = 15;
+
+/// The slice of a sample history that a graph is currently showing
+#[derive(Clone, Copy)]
+pub struct Viewport {
+    width: usize,
+    // How many of the newest samples are hidden off the right edge, 0 when following live data
+    offset: usize,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            width: DEFAULT_WINDOW,
+            offset: 0,
+        }
+    }
+}
+
+impl Viewport {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    // Pan with [ and ], zoom with + and -, returning whether the key was used
+    pub fn handle_key(&mut self, code: KeyCode, available: usize) -> bool {
+        let step = (self.width / 4).max(1);
+        match code {
+            KeyCode::Char('[') => self.offset += step,
+            KeyCode::Char(']') => self.offset = self.offset.saturating_sub(step),
+            KeyCode::Char('+') => self.width = (self.width / 2).max(MIN_WINDOW),
+            KeyCode::Char('-') => self.width = (self.width * 2).min(available.max(DEFAULT_WINDOW)),
+            _ => return false,
+        }
+
+        // Don't let panning run past the oldest sample we still have
+        self.offset = self.offset.min(available.saturating_sub(self.width));
+        true
+    }
+
+    // Indices of the samples inside the viewport
+    pub fn range(&self, len: usize) -> Range<usize> {
+        let end = len.saturating_sub(self.offset);
+        end.saturating_sub(self.width)..end
+    }
+
+    pub fn x_bounds(&self) -> [f64; 2] {
+        [0.0, self.width as f64]
+    }
+
+    // Graph title annotation describing the viewport
+    pub fn suffix(&self) -> String {
+        if self.offset == 0 && self.width == DEFAULT_WINDOW {
+            " ([ ] pan, +/- zoom)".to_string()
+        } else {
+            format!(" ({} samples, {} back)", self.width, self.offset)
+        }
+    }
+}
+
+/// Which of a module's inputs has keyboard focus, if any
+///
+/// Tab and Shift-Tab cycle through the inputs and back round to none, so keys can reach the module's shortcuts
+/// again. Esc also drops focus.
+#[derive(Default, Clone, Copy)]
+pub struct Focus {
+    current: Option<usize>,
+}
+
+impl Focus {
+    // Move focus among `count` inputs, returning whether the key was used
+    pub fn handle_key(&mut self, code: KeyCode, count: usize) -> bool {
+        self.current = match (code, self.current) {
+            (KeyCode::Tab, None) if count > 0 => Some(0),
+            (KeyCode::Tab, Some(index)) => (index + 1 < count).then_some(index + 1),
+            (KeyCode::BackTab, None) => count.checked_sub(1),
+            (KeyCode::BackTab, Some(index)) => index.checked_sub(1),
+            (KeyCode::Esc, Some(_)) => None,
+            _ => return false,
+        };
+        true
+    }
+
+    pub fn is(&self, index: usize) -> bool {
+        self.current == Some(index)
+    }
+
+    pub fn any(&self) -> bool {
+        self.current.is_some()
+    }
+}
 
-#[derive(Default)]
 pub struct SampleBuf<T, const N: usize> {
     samples: VecDeque<T>,
+    times: VecDeque<SystemTime>,
+    // Expected time between samples, one graph slot is this long
+    interval: Duration,
+}
+
+impl<T, const N: usize> Default for SampleBuf<T, N> {
+    fn default() -> Self {
+        Self::with_interval(Duration::from_secs(1))
+    }
+}
+
+impl<T, const N: usize> SampleBuf<T, N> {
+    // Buffer for samples expected every `interval`
+    pub fn with_interval(interval: Duration) -> Self {
+        Self {
+            samples: VecDeque::new(),
+            times: VecDeque::new(),
+            interval,
+        }
+    }
 }
 
 impl<T: Into<f64> + Copy, const N: usize> SampleBuf<T, N> {
     // Insert a sample into the buffer and evict the oldest if full
     pub fn insert(&mut self, sample: T) {
+        self.insert_at(sample, SystemTime::now());
+    }
+
+    fn insert_at(&mut self, sample: T, time: SystemTime) {
         self.samples.push_back(sample);
+        self.times.push_back(time);
         if self.samples.len() > N {
             self.samples.pop_front();
+            self.times.pop_front();
+        }
+    }
+
+    // Converts the samples inside the viewport into a format that ratatui can use
+    // Points are placed by when they were captured, so stalled updates show as a gap rather than squashing time
+    // Samples from before the left edge, which a stall leaves in the viewport's range, aren't drawn.
+    pub fn window(&self, viewport: &Viewport) -> Vec<(f64, f64)> {
+        let Some(origin) = self.origin(viewport) else {
+            return Vec::new();
+        };
+
+        viewport
+            .range(self.samples.len())
+            .filter_map(|i| Some((self.slots_between(origin, self.times[i])?, self.samples[i].into())))
+            .collect()
+    }
+
+    // Time at the left edge of the viewport
+    // While filling up the graph grows from the left, once full the newest sample sits in the last slot
+    fn origin(&self, viewport: &Viewport) -> Option<SystemTime> {
+        let range = viewport.range(self.times.len());
+        let first = *self.times.get(range.start)?;
+        let last = *self.times.get(range.end.checked_sub(1)?)?;
+
+        if range.len() < viewport.width() {
+            Some(first)
+        } else {
+            let span = self.interval * (viewport.width() as u32 - 1);
+            Some(last.checked_sub(span).unwrap_or(first))
         }
     }
 
-    // Converts the buffer into a format that ratatui can use
-    // Probably more efficent way than copying but buffer is small and only called once a second
-    pub fn get(&self) -> Vec<(f64, f64)> {
+    // `None` if `to` is before `from`
+    fn slots_between(&self, from: SystemTime, to: SystemTime) -> Option<f64> {
+        Some(to.duration_since(from).ok()?.as_secs_f64() / self.interval.as_secs_f64())
+    }
+
+    // Iterate over the raw samples inside the viewport, oldest first
+    pub fn window_iter(&self, viewport: &Viewport) -> impl Iterator<Item = &T> {
+        self.samples.range(viewport.range(self.samples.len()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    // Smallest and largest sample currently in the buffer
+    pub fn min_max(&self) -> Option<(f64, f64)> {
         self.samples
             .iter()
-            .enumerate()
-            .map(|(i, &val)| (i as f64, val.into()))
-            .collect()
+            .map(|&s| s.into())
+            .fold(None, |acc, v: f64| match acc {
+                None => Some((v, v)),
+                Some((min, max)) => Some((min.min(v), max.max(v))),
+            })
+    }
+
+    // Iterate over the raw samples, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.samples.iter()
+    }
+
+    // Iterate over the raw samples along with when each was taken, oldest first
+    pub fn iter_with_times(&self) -> impl Iterator<Item = (SystemTime, &T)> {
+        self.times.iter().copied().zip(&self.samples)
+    }
+
+    // Least-squares rate of change per second over the samples taken in the last `span`, by their timestamps
+    // None until there are at least `min_samples` in the span, since a couple of noisy readings give a wild slope
+    pub fn rate_per_sec(&self, span: Duration, min_samples: usize) -> Option<f64> {
+        let latest = *self.times.back()?;
+        let points: Vec<(f64, f64)> = self
+            .times
+            .iter()
+            .zip(&self.samples)
+            .rev()
+            .map(|(&time, &sample)| (latest.duration_since(time).unwrap_or_default(), sample))
+            .take_while(|(age, _)| *age <= span)
+            .map(|(age, sample)| (-age.as_secs_f64(), sample.into()))
+            .collect();
+        if points.len() < min_samples.max(2) {
+            return None;
+        }
+
+        let n = points.len() as f64;
+        let mean_t = points.iter().map(|(t, _)| t).sum::<f64>() / n;
+        let mean_v = points.iter().map(|(_, v)| v).sum::<f64>() / n;
+        let covariance: f64 = points.iter().map(|(t, v)| (t - mean_t) * (v - mean_v)).sum();
+        let variance: f64 = points.iter().map(|(t, _)| (t - mean_t).powi(2)).sum();
+        (variance > 0.0).then(|| covariance / variance)
+    }
+
+    // Label the start, middle and end of the viewport with the wall-clock time at that position
+    pub fn wall_clock_labels(&self, viewport: &Viewport) -> [Span<'static>; 3] {
+        let origin = self.origin(viewport).unwrap_or_else(SystemTime::now);
+        let width = viewport.width() as u32;
+        [0, width / 2, width]
+            .map(|slot| origin + self.interval * slot)
+            .map(|t| Span::styled(format_wall_clock(t), Style::default().bold()))
+    }
+}
+
+// Read a value that never changes, keeping it once a read succeeds
+// Failed reads are retried on every call, since the first one can race the EC starting up.
+pub fn retry_static<T>(value: &mut color_eyre::Result<T>, read: impl FnOnce() -> color_eyre::Result<T>) {
+    if value.is_err() {
+        *value = read();
+    }
+}
+
+// Drop every color from a rendered buffer, for terminals and captures where color is unwanted
+// Backgrounds become reverse video, so badges and selections still stand out.
+pub fn strip_colors(buf: &mut Buffer) {
+    for cell in &mut buf.content {
+        if cell.bg != Color::Reset {
+            cell.modifier |= Modifier::REVERSED;
+        }
+        cell.fg = Color::Reset;
+        cell.bg = Color::Reset;
+    }
+}
+
+// Ring the terminal bell, ignoring failures since it's only a nicety
+pub fn ring_bell() {
+    use std::io::Write;
+    let mut stdout = std::io::stdout();
+    let _ = stdout.write_all(b"\x07").and_then(|_| stdout.flush());
+}
+
+// Whether clocks are shown in 12-hour format, a display preference shared by every tab
+static TWELVE_HOUR: AtomicBool = AtomicBool::new(false);
+
+pub fn twelve_hour() -> bool {
+    TWELVE_HOUR.load(Ordering::Relaxed)
+}
+
+pub fn set_twelve_hour(enabled: bool) {
+    TWELVE_HOUR.store(enabled, Ordering::Relaxed);
+}
+
+// Whether status markers are plain ASCII rather than emoji, whose width many terminals get wrong
+static ASCII_STATUS: AtomicBool = AtomicBool::new(false);
+
+pub fn ascii_status() -> bool {
+    ASCII_STATUS.load(Ordering::Relaxed)
+}
+
+pub fn set_ascii_status(enabled: bool) {
+    ASCII_STATUS.store(enabled, Ordering::Relaxed);
+}
+
+// Decode a NUL-terminated string field, up to the end of the field if firmware left out the NUL. Anything that
+// isn't printable UTF-8 is shown as hex bytes instead, so binary or mis-encoded data can still be inspected
+pub fn str_from_bytes(raw: &[u8]) -> String {
+    let bytes = raw.iter().position(|&b| b == 0).map_or(raw, |nul| &raw[..nul]);
+    match std::str::from_utf8(bytes) {
+        Ok(s) if !s.chars().any(char::is_control) => s.to_owned(),
+        _ => {
+            let hex: Vec<String> = bytes.iter().map(|b| format!("{b:02x}")).collect();
+            format!("hex: {}", hex.join(" "))
+        }
+    }
+}
+
+// Debug output of a read for the raw values view, or why it failed
+pub fn raw_value<T: std::fmt::Debug>(value: &Result<T, color_eyre::Report>) -> String {
+    match value {
+        Ok(value) => format!("{value:?}"),
+        Err(e) => format!("Error: {e}"),
+    }
+}
+
+// Format a timestamp as local HH:MM:SS, or hh:MM:SS AM/PM with the 12-hour clock
+pub fn format_wall_clock(time: SystemTime) -> String {
+    let format = if twelve_hour() { "%I:%M:%S %p" } else { "%H:%M:%S" };
+    chrono::DateTime::<chrono::Local>::from(time).format(format).to_string()
+}
+
+// Write every sample in `samples` to a CSV file with a timestamp column and a `column` value column
+// Each of `comments` becomes a leading "# " line, for whatever is needed to interpret the values.
+pub fn export_samples_csv<T: Into<f64> + Copy, const N: usize>(
+    path: &std::path::Path,
+    comments: &[String],
+    column: &str,
+    samples: &SampleBuf<T, N>,
+) -> color_eyre::Result<()> {
+    use std::io::Write;
+
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+    for comment in comments {
+        writeln!(writer, "# {comment}")?;
+    }
+    writeln!(writer, "time,{column}")?;
+    for (time, &sample) in samples.iter_with_times() {
+        let time = chrono::DateTime::<chrono::Local>::from(time).format(EXPORT_TIME_FORMAT);
+        writeln!(writer, "{time},{}", sample.into())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// How long a changed value stays highlighted, zero to disable
+static HIGHLIGHT_MS: AtomicU64 = AtomicU64::new(1500);
+
+pub fn set_highlight_duration(duration: Duration) {
+    HIGHLIGHT_MS.store(duration.as_millis() as u64, Ordering::Relaxed);
+}
+
+// Remembers when a value last changed, so the change can be briefly highlighted
+// Meant for values that change rarely; anything graphed changes every update and would always be lit.
+#[derive(Default)]
+pub struct Changed<T> {
+    value: Option<T>,
+    changed_at: Option<Instant>,
+}
+
+impl<T: PartialEq> Changed<T> {
+    // Record the latest value, the first one isn't a change
+    pub fn update(&mut self, value: T) {
+        if self.value.as_ref().is_some_and(|old| *old != value) {
+            self.changed_at = Some(Instant::now());
+        }
+        self.value = Some(value);
+    }
+
+    // Highlight for a recently changed value
+    pub fn style(&self) -> Style {
+        let duration = Duration::from_millis(HIGHLIGHT_MS.load(Ordering::Relaxed));
+        if self.changed_at.is_some_and(|at| at.elapsed() < duration) {
+            Style::default().fg(tailwind::SLATE.c950).bg(tailwind::AMBER.c300)
+        } else {
+            Style::default()
+        }
+    }
+}
+
+// Tracks when a value was last successfully read so stale data can be flagged
+#[derive(Default, Clone, Copy)]
+pub struct Freshness {
+    last_success: Option<Instant>,
+}
+
+impl Freshness {
+    // Record a successful read
+    pub fn mark(&mut self) {
+        self.last_success = Some(Instant::now());
+    }
+
+    // How long the value has been stale, if it has ever been read and is now older than the threshold
+    pub fn stale_for(&self) -> Option<Duration> {
+        self.last_success.map(|t| t.elapsed()).filter(|&age| age >= STALE_AFTER)
+    }
+
+    // Annotation to append to a title, empty when fresh
+    pub fn suffix(&self) -> String {
+        self.stale_for()
+            .map(|age| format!(" (stale {}s)", age.as_secs()))
+            .unwrap_or_default()
+    }
+
+    // Dim stale values
+    pub fn style(&self) -> Style {
+        if self.stale_for().is_some() {
+            Style::default().dim()
+        } else {
+            Style::default()
+        }
     }
 }
 
@@ -46,6 +627,107 @@ pub struct Graph {
     pub y_axis: String,
     pub y_bounds: [f64; 2],
     pub y_labels: [Span<'static>; 3],
+
+    pub markers: Vec<GraphMarker>,
+    /// Further lines plotted under the samples, named in the legend
+    pub series: Vec<GraphSeries>,
+}
+
+/// Extra line plotted on a graph from its own samples, e.g. the commanded fan speed under the tach
+pub struct GraphSeries {
+    pub label: &'static str,
+    pub color: Color,
+    pub samples: Vec<(f64, f64)>,
+}
+
+/// Horizontal reference line drawn across a graph, e.g. a threshold
+pub struct GraphMarker {
+    pub label: &'static str,
+    pub value: f64,
+    pub color: Color,
+}
+
+/// Outcome of reading a value back straight after writing it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Echo<T> {
+    Match,
+    Mismatch { sent: T, read: T },
+    ReadFailed { sent: T },
+}
+
+impl<T: PartialEq + Copy + std::fmt::Display> Echo<T> {
+    pub fn check(sent: T, read: color_eyre::Result<T>) -> Self {
+        match read {
+            Ok(read) if read == sent => Self::Match,
+            Ok(read) => Self::Mismatch { sent, read },
+            Err(_) => Self::ReadFailed { sent },
+        }
+    }
+
+    // Describe a discrepancy, e.g. "set 3000, reads 2950"
+    pub fn mismatch_str(&self, unit: &str) -> Option<String> {
+        match self {
+            Self::Match => None,
+            Self::Mismatch { sent, read } => Some(format!("set {sent}{unit}, reads {read}{unit}")),
+            Self::ReadFailed { sent } => Some(format!("set {sent}{unit}, read back failed")),
+        }
+    }
+}
+
+// Style and title for an input field, greyed out when writes are disabled
+pub fn input_block(title: &str, read_only: bool) -> Block<'static> {
+    if read_only {
+        Block::bordered()
+            .title(format!("{title} (read-only)"))
+            .style(Style::default().fg(Color::DarkGray))
+    } else {
+        Block::bordered().title(title.to_string())
+    }
+}
+
+// parse_numeric for a ValidatedInput, saying what was expected on failure
+pub fn validate_numeric(input: &str) -> Result<u32, String> {
+    parse_numeric(input).ok_or_else(|| "Expected a number: decimal, 0x hex or 0b binary".to_string())
+}
+
+// Parse user input as an unsigned integer, accepting 0x-prefixed hex, 0b-prefixed binary or plain decimal
+// Signs are rejected, which from_str_radix would otherwise take even after the prefix
+pub fn parse_numeric(input: &str) -> Option<u32> {
+    let input = input.trim();
+    if input.starts_with(['+', '-']) {
+        return None;
+    }
+    let (digits, radix) = if let Some(hex) = input.strip_prefix("0x").or_else(|| input.strip_prefix("0X")) {
+        (hex, 16)
+    } else if let Some(bin) = input.strip_prefix("0b").or_else(|| input.strip_prefix("0B")) {
+        (bin, 2)
+    } else {
+        (input, 10)
+    };
+
+    if digits.starts_with(['+', '-']) {
+        return None;
+    }
+    u32::from_str_radix(digits, radix).ok()
+}
+
+// Format a Markdown report section, noting the failure instead of the (stale) data if the read failed
+pub fn report_section<T: std::fmt::Display>(
+    heading: &str,
+    success: bool,
+    lines: impl IntoIterator<Item = T>,
+) -> String {
+    let mut section = format!("### {heading}\n\n");
+    if success {
+        section.push_str("```\n");
+        for line in lines {
+            section.push_str(&format!("{line}\n"));
+        }
+        section.push_str("```\n\n");
+    } else {
+        section.push_str("_Read failed_\n\n");
+    }
+    section
 }
 
 // Convert deciKelvin to degrees Celsius
@@ -74,21 +756,54 @@ pub fn title_block(title: &str, padding: u16, label_color: Color) -> Block<'_> {
         .fg(label_color)
 }
 
-// Combines a title string with a visual status indicator character
+// Combines a title string with a visual status indicator, emoji or ASCII depending on ascii_status()
 pub fn title_str_with_status(title: &str, success: bool) -> String {
-    let status = if success { "✅" } else { "❌" };
+    let status = match (ascii_status(), success) {
+        (false, true) => "✅",
+        (false, false) => "❌",
+        (true, true) => "[OK]",
+        (true, false) => "[ERR]",
+    };
     format!("{title} {status}")
 }
 
 pub fn render_chart(area: Rect, buf: &mut Buffer, graph: Graph) {
     let samples = &graph.samples[..];
-    let datasets = vec![
+    let marker_lines: Vec<[(f64, f64); 2]> = graph
+        .markers
+        .iter()
+        .map(|marker| [(graph.x_bounds[0], marker.value), (graph.x_bounds[1], marker.value)])
+        .collect();
+
+    // Markers go first so the samples are drawn on top of them
+    let mut datasets: Vec<Dataset<'_>> = graph
+        .markers
+        .iter()
+        .zip(&marker_lines)
+        .map(|(marker, line)| {
+            Dataset::default()
+                .name(marker.label)
+                .marker(symbols::Marker::Dot)
+                .style(Style::default().fg(marker.color))
+                .graph_type(GraphType::Line)
+                .data(line)
+        })
+        .collect();
+    datasets.extend(graph.series.iter().map(|series| {
+        Dataset::default()
+            .name(series.label)
+            .marker(symbols::Marker::Braille)
+            .style(Style::default().fg(series.color))
+            .graph_type(GraphType::Line)
+            .data(&series.samples)
+    }));
+    datasets.push(
         Dataset::default()
             .marker(symbols::Marker::Braille)
             .style(Style::default().fg(graph.color))
             .graph_type(GraphType::Line)
             .data(samples),
-    ];
+    );
 
     let chart = Chart::new(datasets)
         .block(Block::bordered().title(Line::from(graph.title).cyan().bold().centered()))
@@ -110,15 +825,131 @@ pub fn render_chart(area: Rect, buf: &mut Buffer, graph: Graph) {
     chart.render(area, buf);
 }
 
-pub fn time_labels(t: usize, max_samples: usize) -> [Span<'static>; 3] {
-    let (start, mid, end) = if t <= max_samples {
-        (0, max_samples / 2, max_samples)
-    } else {
-        (t - max_samples, t - max_samples / 2, t)
-    };
+// Render a compact sparkline, auto-scaled so the y-axis spans the min/max of the samples
+pub fn render_sparkline<T: Into<f64> + Copy, const N: usize>(
+    area: Rect,
+    buf: &mut Buffer,
+    title: String,
+    color: Color,
+    samples: &SampleBuf<T, N>,
+) {
+    let (min, max) = samples.min_max().unwrap_or_default();
+
+    // Offset by one so the minimum sample still draws a visible bar, and keep a flat line at half height
+    let data: Vec<u64> = samples.iter().map(|&s| (s.into() - min) as u64 + 1).collect();
+    let range = ((max - min) as u64 + 1).max(2);
+
+    Sparkline::default()
+        .block(Block::bordered().title(title))
+        .style(Style::default().fg(color))
+        .max(range)
+        .data(&data)
+        .render(area, buf);
+}
+
+pub fn time_labels(t: usize, viewport: &Viewport) -> [Span<'static>; 3] {
+    let width = viewport.width();
+    let end = viewport.range(t).end.max(width);
+    let (start, mid, end) = (end - width, end - width / 2, end);
     [
         Span::styled(start.to_string(), Style::default().bold()),
         Span::styled(mid.to_string(), Style::default().bold()),
         Span::styled(end.to_string(), Style::default().bold()),
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn str_from_bytes_without_nul() {
+        assert_eq!(str_from_bytes(b"ABC"), "ABC");
+        assert_eq!(str_from_bytes(b"AB\0\0"), "AB");
+    }
+
+    #[test]
+    fn str_from_bytes_non_utf8() {
+        assert_eq!(str_from_bytes(&[0x41, 0xff, 0x01, 0x00, 0x42]), "hex: 41 ff 01");
+    }
+
+    #[test]
+    fn parse_numeric_formats() {
+        let cases = [
+            ("42", Some(42)),
+            (" 42 ", Some(42)),
+            ("0x2A", Some(42)),
+            ("0X2a", Some(42)),
+            ("0b101010", Some(42)),
+            ("0B101010", Some(42)),
+            ("4294967295", Some(u32::MAX)),
+            ("0xFFFFFFFF", Some(u32::MAX)),
+            ("4294967296", None),
+            ("0x100000000", None),
+            ("+5", None),
+            ("-5", None),
+            ("0x+5", None),
+            ("0b-1", None),
+            ("0x", None),
+            ("0b2", None),
+            ("12a", None),
+            ("", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_numeric(input), expected, "input {input:?}");
+        }
+    }
+
+    // Buffer holding a sample at each of `seconds` after a fixed start
+    fn samples_at(seconds: &[u64]) -> SampleBuf<u32, 10> {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let mut buf = SampleBuf::with_interval(Duration::from_secs(1));
+        for (i, &second) in seconds.iter().enumerate() {
+            buf.insert_at(i as u32, start + Duration::from_secs(second));
+        }
+        buf
+    }
+
+    #[test]
+    fn window_partly_filled() {
+        let viewport = Viewport { width: 5, offset: 0 };
+        assert_eq!(
+            samples_at(&[0, 1, 2]).window(&viewport),
+            [(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)]
+        );
+    }
+
+    #[test]
+    fn window_after_stall() {
+        // The newest sample sits in the last slot, and those from before the stall fall off the left edge
+        let viewport = Viewport { width: 5, offset: 0 };
+        assert_eq!(
+            samples_at(&[0, 1, 2, 10, 11]).window(&viewport),
+            [(3.0, 3.0), (4.0, 4.0)]
+        );
+    }
+
+    #[test]
+    fn rate_per_sec_too_few_samples() {
+        assert_eq!(samples_at(&[0, 1]).rate_per_sec(Duration::from_secs(60), 3), None);
+        assert_eq!(samples_at(&[0]).rate_per_sec(Duration::from_secs(60), 0), None);
+    }
+
+    #[test]
+    fn rate_per_sec_flat() {
+        let mut buf: SampleBuf<u32, 10> = SampleBuf::with_interval(Duration::from_secs(1));
+        for second in 0..5 {
+            buf.insert_at(40, SystemTime::UNIX_EPOCH + Duration::from_secs(second));
+        }
+        assert_eq!(buf.rate_per_sec(Duration::from_secs(60), 3), Some(0.0));
+    }
+
+    #[test]
+    fn rate_per_sec_linear_ramp() {
+        // Samples rise by one every two seconds
+        let rate = samples_at(&[0, 2, 4, 6, 8])
+            .rate_per_sec(Duration::from_secs(60), 3)
+            .unwrap();
+        assert!((rate - 0.5).abs() < 1e-9, "rate {rate}");
+    }
+}
@@ -0,0 +1,223 @@
+use crate::app::Module;
+use crate::battery::BixData;
+use crate::common;
+use crate::{Source, Threshold};
+use color_eyre::{Result, eyre::eyre};
+use crossterm::event::Event;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style, Stylize, palette::tailwind},
+    text::Text,
+    widgets::{Block, Row, Table, Widget},
+};
+
+const LABEL_COLOR: Color = tailwind::SLATE.c200;
+const DIFF_COLOR: Color = tailwind::ROSE.c300;
+const DATA_NOT_YET_RETRIEVED_MSG: &str = "Data not yet retrieved";
+const NO_DATA: &str = "—";
+
+// Readings of one source that never change, kept once read so they aren't fetched every tick
+struct Static {
+    firmware_version: Result<String>,
+    bix: Result<BixData>,
+}
+
+impl Static {
+    fn new() -> Self {
+        Self {
+            firmware_version: Err(eyre!(DATA_NOT_YET_RETRIEVED_MSG)),
+            bix: Err(eyre!(DATA_NOT_YET_RETRIEVED_MSG)),
+        }
+    }
+
+    fn update<S: Source>(&mut self, source: &S) {
+        common::retry_static(&mut self.firmware_version, || source.get_firmware_version());
+        common::retry_static(&mut self.bix, || source.get_bix());
+    }
+}
+
+// One reading from each source, as displayed
+struct Reading {
+    label: &'static str,
+    live: Result<String>,
+    baseline: Result<String>,
+}
+
+impl Reading {
+    // A read failing on only one side counts as a difference, failing on both doesn't
+    fn differs(&self) -> bool {
+        match (&self.live, &self.baseline) {
+            (Ok(live), Ok(baseline)) => live != baseline,
+            (Err(_), Err(_)) => false,
+            _ => true,
+        }
+    }
+}
+
+/// Read-only side by side view of two sources, e.g. the EC against a dump captured from a known good firmware build
+///
+/// Values are compared as displayed, so a temperature only differs once it does at 0.1 °C. The RTC time is left
+/// out since it would never match.
+pub struct Compare<A: Source, B: Source> {
+    live: A,
+    baseline: B,
+    baseline_name: String,
+    live_static: Static,
+    baseline_static: Static,
+    readings: Vec<Reading>,
+}
+
+impl<A: Source, B: Source> Module for Compare<A, B> {
+    fn title(&self) -> &'static str {
+        "Compare"
+    }
+
+    fn update(&mut self) {
+        self.live_static.update(&self.live);
+        self.baseline_static.update(&self.baseline);
+        self.readings = read_all(&self.live, &self.live_static)
+            .into_iter()
+            .zip(read_all(&self.baseline, &self.baseline_static))
+            .map(|((label, live), (_, baseline))| Reading { label, live, baseline })
+            .collect();
+    }
+
+    fn handle_event(&mut self, _evt: &Event) {}
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        let cell = |value: &Result<String>, differs: bool| {
+            let text = Text::raw(value.as_ref().map_or(NO_DATA.to_string(), String::clone));
+            if differs { text.fg(DIFF_COLOR).bold() } else { text }
+        };
+        let rows = self.readings.iter().map(|reading| {
+            let differs = reading.differs();
+            Row::new(vec![
+                Text::raw(reading.label).add_modifier(Modifier::BOLD),
+                cell(&reading.live, differs),
+                cell(&reading.baseline, differs),
+            ])
+        });
+        let header = Row::new(vec!["Reading", "Live", self.baseline_name.as_str()]).underlined();
+
+        let differences = self.differences().count();
+        let title = common::title_str_with_status(&format!("{differences} differences"), differences == 0);
+        let widths = [
+            Constraint::Percentage(30),
+            Constraint::Percentage(35),
+            Constraint::Percentage(35),
+        ];
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(Block::bordered().title(title).fg(LABEL_COLOR))
+            .style(Style::new().white());
+        Widget::render(table, area, buf);
+    }
+
+    fn report(&self) -> String {
+        let lines: Vec<String> = self
+            .differences()
+            .map(|reading| {
+                format!(
+                    "{}: {} (live) vs {} ({})",
+                    reading.label,
+                    reading.live.as_ref().map_or(NO_DATA, String::as_str),
+                    reading.baseline.as_ref().map_or(NO_DATA, String::as_str),
+                    self.baseline_name
+                )
+            })
+            .collect();
+        if lines.is_empty() {
+            common::report_section("Differences", true, ["None"])
+        } else {
+            common::report_section("Differences", true, lines)
+        }
+    }
+
+    fn raw_values(&self) -> Vec<(String, String)> {
+        self.readings
+            .iter()
+            .map(|reading| {
+                (
+                    reading.label.to_string(),
+                    format!(
+                        "{} | {}",
+                        common::raw_value(&reading.live),
+                        common::raw_value(&reading.baseline)
+                    ),
+                )
+            })
+            .collect()
+    }
+}
+
+impl<A: Source, B: Source> Compare<A, B> {
+    /// Compare `live` against `baseline`, labelling the baseline column with `baseline_name`
+    pub fn new(live: A, baseline: B, baseline_name: String) -> Self {
+        let mut inst = Self {
+            live,
+            baseline,
+            baseline_name,
+            live_static: Static::new(),
+            baseline_static: Static::new(),
+            readings: Vec::new(),
+        };
+
+        inst.update();
+        inst
+    }
+
+    fn differences(&self) -> impl Iterator<Item = &Reading> {
+        self.readings.iter().filter(|reading| reading.differs())
+    }
+}
+
+// Every compared reading, in display order
+// The live side's polled reads come from the tick's cache, so this adds no EC traffic beyond what no tab reads.
+fn read_all<S: Source>(source: &S, statics: &Static) -> Vec<(&'static str, Result<String>)> {
+    let rpm = |rpm: Result<f64>| rpm.map(|rpm| rpm.round().to_string());
+    let temp = |temp: Result<f64>| temp.map(|temp| format!("{temp:.1} °C"));
+    let bst = source.get_bst();
+    let bst_field = |field: fn(&crate::battery::BstData) -> String| bst.as_ref().map(field).map_err(|e| eyre!("{e}"));
+    let bix_field = |field: fn(&BixData) -> String| statics.bix.as_ref().map(field).map_err(|e| eyre!("{e}"));
+
+    vec![
+        (
+            "Firmware version",
+            statics.firmware_version.as_ref().cloned().map_err(|e| eyre!("{e}")),
+        ),
+        ("Temperature", temp(source.get_temperature())),
+        ("Fan RPM", rpm(source.get_rpm())),
+        ("Fan min RPM", rpm(source.get_min_rpm())),
+        ("Fan max RPM", rpm(source.get_max_rpm())),
+        ("Fan on", temp(source.get_threshold(Threshold::On))),
+        ("Fan off", temp(source.get_threshold(Threshold::Off))),
+        ("Fan ramping", temp(source.get_threshold(Threshold::Ramping))),
+        ("Fan max", temp(source.get_threshold(Threshold::Max))),
+        ("Battery state", bst_field(|bst| format!("{:#04x}", bst.state.bits()))),
+        ("Battery rate", bst_field(|bst| bst.rate.to_string())),
+        ("Battery capacity", bst_field(|bst| bst.capacity.to_string())),
+        ("Battery voltage", bst_field(|bst| bst.voltage.to_string())),
+        ("Design capacity", bix_field(|bix| bix.design_capacity.to_string())),
+        (
+            "Last full capacity",
+            bix_field(|bix| bix.last_full_capacity.to_string()),
+        ),
+        ("Cycle count", bix_field(|bix| bix.cycle_count.to_string())),
+        ("Model number", bix_field(|bix| bix.model_number.clone())),
+        ("Serial number", bix_field(|bix| bix.serial_number.clone())),
+        ("Battery trip point", source.get_btp().map(|btp| btp.to_string())),
+        (
+            "Charge current limit",
+            source.get_charge_current_limit().map(|limit| format!("{limit} mA")),
+        ),
+        (
+            "Power source",
+            source.get_power_source().map(|power| power.as_str().to_string()),
+        ),
+        (
+            "USB-C connectors",
+            source.get_connector_count().map(|count| count.to_string()),
+        ),
+    ]
+}
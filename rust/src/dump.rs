@@ -0,0 +1,486 @@
+use crate::{RtcSource, Source, Threshold, UcsiSource, battery, common, ucsi};
+use color_eyre::{Result, eyre::eyre};
+use embedded_mcu_hal::time::{Datetime, Month, UncheckedDatetime};
+use std::{collections::BTreeMap, fmt::Display, fs, path::Path, str::FromStr, sync::Arc};
+use time_alarm_service_messages::{
+    AcpiDaylightSavingsTimeStatus, AcpiTimeZone, AcpiTimerId, AcpiTimestamp, AlarmExpiredWakePolicy, AlarmTimerSeconds,
+    TimeAlarmDeviceCapabilities, TimerStatus,
+};
+
+/// Source replaying values captured from a real device, for UI work without hardware
+///
+/// A dump is a text file of `key=value` lines. A `#` starting the line or following whitespace begins a comment,
+/// so values can still contain one, and blank lines are ignored. Any key that is missing reads back as an error, so
+/// a partial capture only breaks the tabs it doesn't cover.
+///
+/// ```text
+/// fw_version=0.2.0
+/// temperature=41.5            # °C
+/// rpm=3200
+/// commanded_rpm=3500
+/// fan_count=2                 # Optional, 1 if left out
+/// fan.1.rpm=3100              # Tach of each fan after the first, which is rpm
+/// min_rpm=0
+/// max_rpm=6000
+/// threshold.on=28             # °C
+/// threshold.off=25            # Optional, for fan hysteresis
+/// threshold.ramping=40
+/// threshold.max=44
+/// sample_period=100           # Sensor sample period in ms
+/// btp=2000
+/// charge_current_limit=3000   # mA
+/// var.<guid>=1234             # Raw GET_VAR value
+///
+/// bst.state=1                 # Raw _BST state bits
+/// bst.rate=1500
+/// bst.capacity=6200
+/// bst.voltage=11800
+/// psr=1                       # 0 - battery, 1 - AC
+/// battery_ext.manufacture_date=2024-03-15   # Optional, as are the other battery_ext.* keys
+/// battery_ext.serial=123-45-678 lot A7
+/// battery_ext.capacity_c5=9700              # Capacity at C/5, any hour rate may be given
+///
+/// bix.revision=1              # One key per _BIX field, named as in BixData
+/// bix.power_unit=0
+/// bix.design_capacity=10000
+/// ...
+/// bix.swap_cap=0
+///
+/// bif.power_unit=0            # Legacy _BIF instead of bix.*, same field names
+/// ...
+///
+/// rtc.capabilities=0xf7
+/// rtc.time=2026-01-01 12:00:00
+///
+/// ucsi.connectors=2
+/// ucsi.0.connected=1
+/// ucsi.0.data_role=1          # 0 - UFP, 1 - DFP
+/// ucsi.0.power_role=0         # Optional, 0 - sink, 1 - source
+/// ucsi.0.voltage_mv=9000      # Optional, negotiated VBUS voltage
+/// ucsi.0.alt_modes=ff01:00000c05,8087:00000001
+/// ucsi.0.rdo=0x2204b12c       # Raw Request Data Object
+/// ucsi.0.cable.e_marked=1
+/// ucsi.0.cable.current_ma=5000
+/// ucsi.0.cable.voltage_mv=20000
+/// ucsi.0.cable.active=0       # 0 - passive, 1 - active
+/// ```
+///
+/// Writes are refused, the dump is a fixed snapshot.
+#[derive(Clone)]
+pub struct Dump {
+    // Shared so clones are cheap, Arc so a clone can be handed to the benchmark's worker thread
+    values: Arc<BTreeMap<String, String>>,
+}
+
+impl Dump {
+    /// Load a dump file
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path).map_err(|e| eyre!("Failed to read {}: {e}", path.display()))?;
+        Self::parse(&contents, &path.display().to_string())
+    }
+
+    // Parse the contents of a dump, naming it `origin` in errors
+    fn parse(contents: &str, origin: &str) -> Result<Self> {
+        let mut values = BTreeMap::new();
+        for (index, line) in contents.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| eyre!("{origin}:{}: expected key=value", index + 1))?;
+            values.insert(key.trim().to_string(), value.trim().to_string());
+        }
+
+        Ok(Self {
+            values: Arc::new(values),
+        })
+    }
+
+    fn get_str(&self, key: &str) -> Result<&str> {
+        self.values
+            .get(key)
+            .map(String::as_str)
+            .ok_or_else(|| crate::unsupported(format!("{key} not in dump")))
+    }
+
+    fn get<T: FromStr>(&self, key: &str) -> Result<T>
+    where
+        T::Err: Display,
+    {
+        let value = self.get_str(key)?;
+        value.parse().map_err(|e| eyre!("{key}: invalid value {value}: {e}"))
+    }
+
+    // Integers may be written in hex or binary too, as they often are when copied out of firmware logs
+    fn get_u32(&self, key: &str) -> Result<u32> {
+        let value = self.get_str(key)?;
+        common::parse_numeric(value).ok_or_else(|| eyre!("{key}: invalid value {value}"))
+    }
+
+    fn read_only<T>(&self, operation: &str) -> Result<T> {
+        Err(eyre!("{operation} not supported: dump source is read-only"))
+    }
+}
+
+// A comment starts at a # opening the line or following whitespace, so values such as serial numbers can hold #
+fn strip_comment(line: &str) -> &str {
+    let start = line
+        .char_indices()
+        .find(|&(index, c)| c == '#' && line[..index].chars().next_back().is_none_or(char::is_whitespace))
+        .map_or(line.len(), |(index, _)| index);
+    &line[..start]
+}
+
+// `YYYY-MM-DD HH:MM:SS`, which is how the RTC tab displays time in 24-hour mode
+fn parse_datetime(value: &str) -> Option<Datetime> {
+    let (date, time) = value.split_once(' ')?;
+    let mut date = date.split('-');
+    let mut time = time.split(':');
+    let datetime = UncheckedDatetime {
+        year: date.next()?.parse().ok()?,
+        month: Month::try_from(date.next()?.parse::<u8>().ok()?).ok()?,
+        day: date.next()?.parse().ok()?,
+        hour: time.next()?.parse().ok()?,
+        minute: time.next()?.parse().ok()?,
+        second: time.next()?.parse().ok()?,
+        ..Default::default()
+    };
+    Datetime::new(datetime).ok()
+}
+
+impl Source for Dump {
+    fn get_temperature(&self) -> Result<f64> {
+        self.get("temperature")
+    }
+
+    fn get_rpm(&self) -> Result<f64> {
+        self.get("rpm")
+    }
+
+    fn get_fan_count(&self) -> Result<u8> {
+        if self.values.contains_key("fan_count") {
+            self.get("fan_count")
+        } else {
+            Ok(1)
+        }
+    }
+
+    fn get_fan_rpm(&self, fan: u8) -> Result<f64> {
+        if fan == 0 {
+            self.get_rpm()
+        } else {
+            self.get(&format!("fan.{fan}.rpm"))
+        }
+    }
+
+    fn get_commanded_rpm(&self) -> Result<f64> {
+        self.get("commanded_rpm")
+    }
+
+    fn has_commanded_rpm(&self) -> bool {
+        self.values.contains_key("commanded_rpm")
+    }
+
+    fn get_min_rpm(&self) -> Result<f64> {
+        self.get("min_rpm")
+    }
+
+    fn get_max_rpm(&self) -> Result<f64> {
+        self.get("max_rpm")
+    }
+
+    fn get_threshold(&self, threshold: Threshold) -> Result<f64> {
+        match threshold {
+            Threshold::On => self.get("threshold.on"),
+            Threshold::Off => self.get("threshold.off"),
+            Threshold::Ramping => self.get("threshold.ramping"),
+            Threshold::Max => self.get("threshold.max"),
+        }
+    }
+
+    fn get_sample_period(&self) -> Result<u32> {
+        self.get_u32("sample_period")
+    }
+
+    fn set_sample_period(&self, _period_ms: u32) -> Result<()> {
+        self.read_only("SET_SAMPLE_PERIOD")
+    }
+
+    fn set_rpm(&self, _rpm: f64) -> Result<()> {
+        self.read_only("SET_RPM")
+    }
+
+    fn clear_rpm_override(&self) -> Result<()> {
+        self.read_only("CLEAR_RPM_OVERRIDE")
+    }
+
+    fn get_bst(&self) -> Result<battery::BstData> {
+        Ok(battery::BstData {
+            state: battery::ChargeState::from_bits(self.get_u32("bst.state")?),
+            rate: self.get_u32("bst.rate")?,
+            capacity: self.get_u32("bst.capacity")?,
+            voltage: self.get_u32("bst.voltage")?,
+        })
+    }
+
+    fn get_bix(&self) -> Result<battery::BixData> {
+        Ok(battery::BixData {
+            revision: self.get_u32("bix.revision")?,
+            power_unit: battery::PowerUnit::try_from(self.get_u32("bix.power_unit")?)?,
+            design_capacity: self.get_u32("bix.design_capacity")?,
+            last_full_capacity: self.get_u32("bix.last_full_capacity")?,
+            battery_technology: battery::BatteryTechnology::try_from(self.get_u32("bix.battery_technology")?)?,
+            design_voltage: self.get_u32("bix.design_voltage")?,
+            warning_capacity: self.get_u32("bix.warning_capacity")?,
+            low_capacity: self.get_u32("bix.low_capacity")?,
+            cycle_count: self.get_u32("bix.cycle_count")?,
+            accuracy: self.get_u32("bix.accuracy")?,
+            max_sample_time: self.get_u32("bix.max_sample_time")?,
+            min_sample_time: self.get_u32("bix.min_sample_time")?,
+            max_average_interval: self.get_u32("bix.max_average_interval")?,
+            min_average_interval: self.get_u32("bix.min_average_interval")?,
+            capacity_gran1: self.get_u32("bix.capacity_gran1")?,
+            capacity_gran2: self.get_u32("bix.capacity_gran2")?,
+            model_number: self.get("bix.model_number")?,
+            serial_number: self.get("bix.serial_number")?,
+            battery_type: self.get("bix.battery_type")?,
+            oem_info: self.get("bix.oem_info")?,
+            swap_cap: battery::SwapCap::try_from(self.get_u32("bix.swap_cap")?)?,
+        })
+    }
+
+    fn get_bif(&self) -> Result<battery::BifData> {
+        Ok(battery::BifData {
+            power_unit: battery::PowerUnit::try_from(self.get_u32("bif.power_unit")?)?,
+            design_capacity: self.get_u32("bif.design_capacity")?,
+            last_full_capacity: self.get_u32("bif.last_full_capacity")?,
+            battery_technology: battery::BatteryTechnology::try_from(self.get_u32("bif.battery_technology")?)?,
+            design_voltage: self.get_u32("bif.design_voltage")?,
+            warning_capacity: self.get_u32("bif.warning_capacity")?,
+            low_capacity: self.get_u32("bif.low_capacity")?,
+            capacity_gran1: self.get_u32("bif.capacity_gran1")?,
+            capacity_gran2: self.get_u32("bif.capacity_gran2")?,
+            model_number: self.get("bif.model_number")?,
+            serial_number: self.get("bif.serial_number")?,
+            battery_type: self.get("bif.battery_type")?,
+            oem_info: self.get("bif.oem_info")?,
+        })
+    }
+
+    fn get_battery_extended(&self) -> Result<battery::BatteryExtended> {
+        let rate_capacities = self
+            .values
+            .iter()
+            .filter_map(|(key, value)| {
+                let hours = key.strip_prefix("battery_ext.capacity_c")?.parse().ok()?;
+                Some((hours, common::parse_numeric(value)?))
+            })
+            .collect();
+        let extended = battery::BatteryExtended {
+            manufacture_date: self.get("battery_ext.manufacture_date").ok(),
+            serial_extended: self.get("battery_ext.serial").ok(),
+            rate_capacities,
+        };
+
+        if extended.manufacture_date.is_none()
+            && extended.serial_extended.is_none()
+            && extended.rate_capacities.is_empty()
+        {
+            Err(crate::unsupported("battery_ext not in dump"))
+        } else {
+            Ok(extended)
+        }
+    }
+
+    fn get_power_source(&self) -> Result<battery::PowerSource> {
+        battery::PowerSource::try_from(self.get_u32("psr")?)
+    }
+
+    fn set_btp(&self, _trippoint: u32) -> Result<()> {
+        self.read_only("SET_BTP")
+    }
+
+    fn get_btp(&self) -> Result<u32> {
+        self.get_u32("btp")
+    }
+
+    fn get_charge_current_limit(&self) -> Result<u32> {
+        self.get_u32("charge_current_limit")
+    }
+
+    fn set_charge_current_limit(&self, _limit_ma: u32) -> Result<()> {
+        self.read_only("SET_CHARGE_CURRENT_LIMIT")
+    }
+
+    fn get_var(&self, guid: uuid::Uuid) -> Result<u32> {
+        self.get_u32(&format!("var.{guid}"))
+    }
+
+    fn set_var(&self, _guid: uuid::Uuid, _value: u32) -> Result<()> {
+        self.read_only("SET_VAR")
+    }
+
+    fn get_firmware_version(&self) -> Result<String> {
+        self.get("fw_version")
+    }
+}
+
+impl RtcSource for Dump {
+    fn get_capabilities(&self) -> Result<TimeAlarmDeviceCapabilities> {
+        Ok(TimeAlarmDeviceCapabilities(self.get_u32("rtc.capabilities")?))
+    }
+
+    fn get_real_time(&self) -> Result<AcpiTimestamp> {
+        let value = self.get_str("rtc.time")?;
+        let datetime = parse_datetime(value).ok_or_else(|| eyre!("rtc.time: invalid value {value}"))?;
+        Ok(AcpiTimestamp {
+            datetime,
+            time_zone: AcpiTimeZone::Unknown,
+            dst_status: AcpiDaylightSavingsTimeStatus::NotObserved,
+        })
+    }
+
+    fn get_wake_status(&self, _timer_id: AcpiTimerId) -> Result<TimerStatus> {
+        Err(crate::unsupported("Timers are not captured in dumps"))
+    }
+
+    fn get_expired_timer_wake_policy(&self, _timer_id: AcpiTimerId) -> Result<AlarmExpiredWakePolicy> {
+        Err(crate::unsupported("Timers are not captured in dumps"))
+    }
+
+    fn get_timer_value(&self, _timer_id: AcpiTimerId) -> Result<AlarmTimerSeconds> {
+        Err(crate::unsupported("Timers are not captured in dumps"))
+    }
+
+    fn set_timer_value(&self, _timer_id: AcpiTimerId, _seconds: AlarmTimerSeconds) -> Result<()> {
+        self.read_only("SET_TIMER_VALUE")
+    }
+
+    fn set_expired_timer_wake_policy(&self, _timer_id: AcpiTimerId, _policy: AlarmExpiredWakePolicy) -> Result<()> {
+        self.read_only("SET_TIMER_WAKE_POLICY")
+    }
+}
+
+impl UcsiSource for Dump {
+    fn get_connector_count(&self) -> Result<u8> {
+        self.get("ucsi.connectors")
+    }
+
+    fn get_alternate_modes(&self, connector: u8) -> Result<Vec<ucsi::AltMode>> {
+        let key = format!("ucsi.{connector}.alt_modes");
+        let value = self.get_str(&key)?;
+
+        // Comma separated svid:vdo pairs in hex
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|mode| !mode.is_empty())
+            .map(|mode| {
+                let (svid, vdo) = mode.split_once(':')?;
+                Some(ucsi::AltMode {
+                    svid: u16::from_str_radix(svid, 16).ok()?,
+                    vdo: u32::from_str_radix(vdo, 16).ok()?,
+                })
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| eyre!("{key}: invalid value {value}"))
+    }
+
+    fn get_connector_status(&self, connector: u8) -> Result<ucsi::ConnectorStatus> {
+        Ok(ucsi::ConnectorStatus {
+            connected: self.get_u32(&format!("ucsi.{connector}.connected"))? != 0,
+            data_role: ucsi::DataRole::try_from(self.get_u32(&format!("ucsi.{connector}.data_role"))?)?,
+            power_role: self
+                .get_u32(&format!("ucsi.{connector}.power_role"))
+                .ok()
+                .map(ucsi::PowerRole::try_from)
+                .transpose()?,
+            voltage_mv: self.get_u32(&format!("ucsi.{connector}.voltage_mv")).ok(),
+        })
+    }
+
+    fn get_current_rdo(&self, connector: u8) -> Result<ucsi::Rdo> {
+        Ok(ucsi::Rdo(self.get_u32(&format!("ucsi.{connector}.rdo"))?))
+    }
+
+    fn get_cable_property(&self, connector: u8) -> Result<ucsi::CableProperty> {
+        Ok(ucsi::CableProperty {
+            e_marked: self.get_u32(&format!("ucsi.{connector}.cable.e_marked"))? != 0,
+            max_current_ma: self.get_u32(&format!("ucsi.{connector}.cable.current_ma"))?,
+            max_voltage_mv: self.get_u32(&format!("ucsi.{connector}.cable.voltage_mv"))?,
+            active: self.get_u32(&format!("ucsi.{connector}.cable.active"))? != 0,
+        })
+    }
+
+    fn connector_reset(&self, _connector: u8, _hard: bool) -> Result<()> {
+        self.read_only("CONNECTOR_RESET")
+    }
+
+    fn swap_data_role(&self, _connector: u8) -> Result<()> {
+        self.read_only("SET_UOR")
+    }
+
+    fn get_error_status(&self, _connector: u8) -> Result<ucsi::ErrorStatus> {
+        Err(crate::unsupported("Error status is not captured in dumps"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_strips_comments() {
+        let dump = Dump::parse(
+            "# Captured on the bench\n\
+             temperature=45.5 # degrees C\n\
+             model_number=BAT#1\n\
+             serial_number=  #7\n\
+             \n\
+             rpm = 2500\t# tach\n",
+            "test",
+        )
+        .unwrap();
+        assert_eq!(dump.get_str("temperature").unwrap(), "45.5");
+        assert_eq!(dump.get_str("model_number").unwrap(), "BAT#1");
+        assert_eq!(dump.get_str("serial_number").unwrap(), "");
+        assert_eq!(dump.get_str("rpm").unwrap(), "2500");
+    }
+
+    #[test]
+    fn load_rejects_line_without_value() {
+        let error = Dump::parse("temperature=45\nrpm\n", "test").err().unwrap();
+        assert_eq!(error.to_string(), "test:2: expected key=value");
+    }
+
+    #[test]
+    fn parse_datetime_valid() {
+        let datetime = parse_datetime("2025-06-01 13:45:30").unwrap();
+        assert_eq!(
+            (
+                datetime.year(),
+                u8::from(datetime.month()),
+                datetime.day(),
+                datetime.hour(),
+                datetime.minute(),
+                datetime.second()
+            ),
+            (2025, 6, 1, 13, 45, 30)
+        );
+    }
+
+    #[test]
+    fn parse_datetime_invalid() {
+        for value in [
+            "2025-06-01",
+            "2025-06-01T13:45:30",
+            "2025-13-01 13:45:30",
+            "2025-06-01 13:45",
+            "now",
+        ] {
+            assert!(parse_datetime(value).is_none(), "value {value:?}");
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use crate::Source;
+use crate::app::Module;
+use crate::common;
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Stylize, palette::tailwind},
+    text::Line,
+    widgets::{Paragraph, Widget},
+};
+use std::collections::VecDeque;
+use std::sync::mpsc::Receiver;
+use std::time::SystemTime;
+
+const LABEL_COLOR: Color = tailwind::SLATE.c200;
+// Oldest events are dropped beyond this, so a chatty EC can't grow the log forever
+const MAX_EVENTS: usize = 1000;
+
+/// Ids the mock source raises events with
+///
+/// The real EC reports whatever code its firmware notifies with, and the
+/// firmware doesn't publish a list, so only these get a description and
+/// anything else is logged with its raw id.
+pub mod id {
+    pub const BATTERY_STATUS: u32 = 0x01;
+    pub const BATTERY_INFO: u32 = 0x02;
+    pub const POWER_SOURCE: u32 = 0x03;
+    pub const THERMAL_THRESHOLD: u32 = 0x10;
+    pub const UCSI_CONNECTOR_CHANGE: u32 = 0x20;
+
+    /// What an event id means, if it's one we know
+    pub fn describe(id: u32) -> Option<&'static str> {
+        match id {
+            BATTERY_STATUS => Some("Battery status changed"),
+            BATTERY_INFO => Some("Battery information changed"),
+            POWER_SOURCE => Some("Power source changed"),
+            THERMAL_THRESHOLD => Some("Thermal threshold crossed"),
+            UCSI_CONNECTOR_CHANGE => Some("USB-C connector changed"),
+            _ => None,
+        }
+    }
+}
+
+/// Asynchronous notification from the EC
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EcEvent {
+    pub id: u32,
+    /// When the event was received, rather than when the UI got round to it
+    pub time: SystemTime,
+}
+
+impl EcEvent {
+    pub fn new(id: u32) -> Self {
+        Self {
+            id,
+            time: SystemTime::now(),
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{}  {:#04x}  {}",
+            common::format_wall_clock(self.time),
+            self.id,
+            id::describe(self.id).unwrap_or("Unknown event")
+        )
+    }
+}
+
+/// Chronological log of every event the EC has raised since startup
+pub struct Events {
+    // None if the source can't deliver events
+    events: Option<Receiver<EcEvent>>,
+    // Most recent first
+    log: VecDeque<EcEvent>,
+    // Lines scrolled down from the most recent
+    scroll: usize,
+}
+
+impl Module for Events {
+    fn title(&self) -> &'static str {
+        "EC Events"
+    }
+
+    // Events arrive through poll_notifications, there's nothing to read on a tick
+    fn update(&mut self) {}
+
+    fn handle_event(&mut self, evt: &Event) {
+        let Event::Key(key) = evt else {
+            return;
+        };
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+
+        let last = self.log.len().saturating_sub(1);
+        match key.code {
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down => self.scroll = (self.scroll + 1).min(last),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::PageDown => self.scroll = (self.scroll + 10).min(last),
+            KeyCode::Home => self.scroll = 0,
+            KeyCode::Char('c') => {
+                self.log.clear();
+                self.scroll = 0;
+            }
+            _ => {}
+        }
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        let title = match &self.events {
+            Some(_) => format!("Events ({}) | ↑ ↓ scroll | c clear", self.log.len()),
+            None => "Events".to_string(),
+        };
+        let lines: Vec<Line<'static>> = match &self.events {
+            None => vec![Line::raw("Events not supported by this source").italic()],
+            Some(_) if self.log.is_empty() => vec![Line::raw("No events received yet").italic()],
+            Some(_) => self
+                .log
+                .iter()
+                .skip(self.scroll)
+                .map(|event| Line::raw(event.describe()))
+                .collect(),
+        };
+        Paragraph::new(lines)
+            .block(common::title_block(&title, 0, LABEL_COLOR))
+            .render(area, buf);
+    }
+
+    fn report(&self) -> String {
+        let lines = self.log.iter().map(EcEvent::describe);
+        common::report_section("EC Events", self.events.is_some(), lines)
+    }
+
+    fn copy_value(&self) -> Option<String> {
+        self.log.front().map(EcEvent::describe)
+    }
+
+    fn commands(&self) -> Vec<(&'static str, KeyCode)> {
+        vec![("Clear event log", KeyCode::Char('c'))]
+    }
+
+    fn poll_notifications(&mut self) -> bool {
+        let Some(events) = &self.events else {
+            return false;
+        };
+
+        let mut received = false;
+        for event in events.try_iter() {
+            log::info!("EC event {}", event.describe());
+            self.log.push_front(event);
+            // Keep the lines being read in place as new ones arrive above them
+            if self.scroll > 0 {
+                self.scroll += 1;
+            }
+            received = true;
+        }
+        self.log.truncate(MAX_EVENTS);
+        self.scroll = self.scroll.min(self.log.len().saturating_sub(1));
+        received
+    }
+}
+
+impl Events {
+    pub fn new<S: Source>(source: &S) -> Self {
+        Self {
+            events: source.subscribe_ec_events(),
+            log: VecDeque::new(),
+            scroll: 0,
+        }
+    }
+}
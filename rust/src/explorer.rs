@@ -0,0 +1,194 @@
+use crate::Source;
+use crate::app::Module;
+use crate::common;
+use crate::widgets::validated_input::ValidatedInput;
+use color_eyre::{Result, eyre::eyre};
+use ratatui::{
+    buffer::Buffer,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::{Direction, Rect},
+    style::{Color, palette::tailwind},
+    text::Line,
+    widgets::{Paragraph, Widget},
+};
+
+const LABEL_COLOR: Color = tailwind::SLATE.c200;
+const MAX_HISTORY: usize = 20;
+
+// A single GET_VAR/SET_VAR request parsed from user input
+enum VarRequest {
+    Get(uuid::Uuid),
+    Set(uuid::Uuid, u32),
+    // A negative value, written and read back as signed
+    SetSigned(uuid::Uuid, i32),
+}
+
+impl VarRequest {
+    // Input is either `<guid>` to read or `<guid>=<value>` to write
+    fn parse(input: &str) -> Result<Self> {
+        let (guid, value) = match input.split_once('=') {
+            Some((guid, value)) => (guid, Some(value)),
+            None => (input, None),
+        };
+
+        let guid = uuid::Uuid::parse_str(guid.trim()).map_err(|e| eyre!("Invalid GUID: {e}"))?;
+        match value {
+            Some(value) => {
+                let invalid = || eyre!("Invalid value: {}", value.trim());
+                match value.trim().strip_prefix('-') {
+                    Some(magnitude) => {
+                        let magnitude = common::parse_numeric(magnitude).ok_or_else(invalid)?;
+                        let value = i32::try_from(-i64::from(magnitude)).map_err(|_| invalid())?;
+                        Ok(Self::SetSigned(guid, value))
+                    }
+                    None => Ok(Self::Set(guid, common::parse_numeric(value).ok_or_else(invalid)?)),
+                }
+            }
+            None => Ok(Self::Get(guid)),
+        }
+    }
+}
+
+// Show a value in decimal and hex, plus how it reads as signed when that differs, e.g. for offsets and deltas
+fn format_value(value: u32) -> String {
+    let signed = value as i32;
+    if signed < 0 {
+        format!("{value} ({value:#010x}, {signed} signed)")
+    } else {
+        format!("{value} ({value:#010x})")
+    }
+}
+
+// Format a GUID along with its well-known name, if any
+fn describe(guid: &uuid::Uuid) -> String {
+    match common::guid::name(guid) {
+        Some(name) => format!("{name} ({guid})"),
+        None => guid.to_string(),
+    }
+}
+
+pub struct Explorer<S: Source> {
+    input: ValidatedInput<VarRequest>,
+    focus: common::Focus,
+    // Most recent result first
+    history: Vec<(String, bool)>,
+    source: S,
+}
+
+impl<S: Source> Module for Explorer<S> {
+    fn title(&self) -> &'static str {
+        "Variable Explorer"
+    }
+
+    fn update(&mut self) {}
+
+    fn handle_event(&mut self, evt: &Event) {
+        // Reads are still allowed in read-only mode, so the input is always there
+        if let Event::Key(key) = evt
+            && key.kind == KeyEventKind::Press
+            && self.focus.handle_key(key.code, 1)
+        {
+            // Focus moved
+        } else if let Event::Key(key) = evt
+            && key.code == KeyCode::Enter
+            && key.kind == KeyEventKind::Press
+            && self.focus.is(0)
+        {
+            if let Some(request) = self.input.submit() {
+                let result = self.execute(request);
+                self.history.insert(0, result);
+                self.history.truncate(MAX_HISTORY);
+            }
+        } else if self.focus.is(0) {
+            self.input.handle_event(evt);
+        }
+    }
+
+    fn has_focus(&self) -> bool {
+        self.focus.any()
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        let [input_area, history_area] = common::area_split(area, Direction::Vertical, 20, 80);
+        self.render_input(input_area, buf);
+        self.render_history(history_area, buf);
+    }
+
+    fn copy_value(&self) -> Option<String> {
+        self.history
+            .first()
+            .filter(|(_, success)| *success)
+            .map(|(result, _)| result.clone())
+    }
+
+    fn report(&self) -> String {
+        common::report_section("Recent Requests", true, self.history_lines())
+    }
+}
+
+impl<S: Source> Explorer<S> {
+    pub fn new(source: S) -> Self {
+        Self {
+            input: ValidatedInput::new(|input| VarRequest::parse(input).map_err(|e| e.to_string())),
+            focus: common::Focus::default(),
+            history: Vec::new(),
+            source,
+        }
+    }
+
+    fn execute(&self, request: VarRequest) -> (String, bool) {
+        match request {
+            VarRequest::Get(guid) => match self.source.get_var(guid) {
+                Ok(value) => (format!("GET {} = {}", describe(&guid), format_value(value)), true),
+                Err(e) => (format!("GET {} failed: {e}", describe(&guid)), false),
+            },
+            VarRequest::Set(guid, value) => match self.source.set_var(guid, value) {
+                Ok(()) => {
+                    // Read straight back so writes the EC clamps or ignores are visible
+                    let echo = common::Echo::check(value, self.source.get_var(guid));
+                    let message = format!("SET {} = {}", describe(&guid), format_value(value));
+                    match echo.mismatch_str("") {
+                        Some(mismatch) => (format!("{message}, mismatch: {mismatch}"), false),
+                        None => (message, true),
+                    }
+                }
+                Err(e) => (format!("SET {} = {value} failed: {e}", describe(&guid)), false),
+            },
+            VarRequest::SetSigned(guid, value) => match self.source.set_var_signed(guid, value) {
+                Ok(()) => {
+                    // Compare signed, so a mismatch reads e.g. "set -10, reads -8"
+                    let echo = common::Echo::check(value, self.source.get_var_signed(guid));
+                    let message = format!("SET {} = {}", describe(&guid), format_value(value as u32));
+                    match echo.mismatch_str("") {
+                        Some(mismatch) => (format!("{message}, mismatch: {mismatch}"), false),
+                        None => (message, true),
+                    }
+                }
+                Err(e) => (format!("SET {} = {value} failed: {e}", describe(&guid)), false),
+            },
+        }
+    }
+
+    fn history_lines(&self) -> Vec<String> {
+        self.history
+            .iter()
+            .map(|(message, success)| common::title_str_with_status(message, *success))
+            .collect()
+    }
+
+    fn render_input(&self, area: Rect, buf: &mut Buffer) {
+        self.input.render(
+            "GUID to read | GUID=value to write <ENTER>",
+            self.source.is_read_only(),
+            self.focus.is(0),
+            area,
+            buf,
+        );
+    }
+
+    fn render_history(&self, area: Rect, buf: &mut Buffer) {
+        let title = common::title_block("Results", 0, LABEL_COLOR);
+        let lines: Vec<Line<'_>> = self.history_lines().into_iter().map(Line::raw).collect();
+        Paragraph::new(lines).block(title).render(area, buf);
+    }
+}
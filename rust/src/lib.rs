@@ -1,4 +1,4 @@
-use color_eyre::Result;
+use color_eyre::{Report, Result};
 
 use time_alarm_service_messages::{
     AcpiTimerId, AcpiTimestamp, AlarmExpiredWakePolicy, AlarmTimerSeconds, TimeAlarmDeviceCapabilities, TimerStatus,
@@ -12,20 +12,70 @@ pub mod mock;
 
 pub mod app;
 pub mod battery;
+pub mod bench;
+pub mod cli;
 pub mod common;
+pub mod compare;
+pub mod dump;
+pub mod events;
+pub mod explorer;
+pub mod logging;
+pub mod overview;
+pub mod persist;
+pub mod proxy;
+pub mod record;
 pub mod rtc;
+pub mod screenshot;
 pub mod thermal;
 pub mod ucsi;
+pub mod web;
 pub mod widgets;
 
+/// Error for a read the source doesn't implement at all, as opposed to one that failed
+///
+/// The proxy stops polling reads that fail this way, rather than pausing and retrying them like failures.
+#[derive(Debug)]
+pub struct Unsupported(pub String);
+
+impl std::fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for Unsupported {}
+
+/// Report an [`Unsupported`] read, described by `message`
+pub fn unsupported(message: impl Into<String>) -> Report {
+    Report::new(Unsupported(message.into()))
+}
+
 /// Trait implemented by all data sources
-pub trait Source: Clone + RtcSource {
+pub trait Source: Clone + RtcSource + UcsiSource {
     /// Get current temperature
     fn get_temperature(&self) -> Result<f64>;
 
-    /// Get current fan RPM
+    /// Get current fan RPM, as measured by the tach
     fn get_rpm(&self) -> Result<f64>;
 
+    /// Get the number of fans, the first of which is the one [`Source::get_rpm`] and the fan controls act on
+    fn get_fan_count(&self) -> Result<u8>;
+
+    /// Get the tach RPM of one fan, numbered from 0
+    fn get_fan_rpm(&self, fan: u8) -> Result<f64>;
+
+    /// Get the fan RPM currently being commanded, which the measured RPM lags behind
+    ///
+    /// Only available if [`Source::has_commanded_rpm`] says so.
+    fn get_commanded_rpm(&self) -> Result<f64> {
+        Err(unsupported("Commanded RPM not supported by this source"))
+    }
+
+    /// Whether the EC reports the commanded RPM separately from the tach, so callers don't poll for errors
+    fn has_commanded_rpm(&self) -> bool {
+        false
+    }
+
     /// Get min fan RPM
     fn get_min_rpm(&self) -> Result<f64>;
 
@@ -35,17 +85,80 @@ pub trait Source: Clone + RtcSource {
     /// Get fan threshold
     fn get_threshold(&self, threshold: Threshold) -> Result<f64>;
 
+    /// Get how often the EC samples the temperature sensor, in milliseconds
+    fn get_sample_period(&self) -> Result<u32>;
+
+    /// Set how often the EC samples the temperature sensor, in milliseconds
+    fn set_sample_period(&self, period_ms: u32) -> Result<()>;
+
     /// Set fan RPM limit
     fn set_rpm(&self, rpm: f64) -> Result<()>;
 
+    /// Clear a manual fan RPM override, handing control back to the EC
+    fn clear_rpm_override(&self) -> Result<()>;
+
     /// Get battery BST data
     fn get_bst(&self) -> Result<battery::BstData>;
 
     /// Get battery BIX data
     fn get_bix(&self) -> Result<battery::BixData>;
 
+    /// Get legacy battery BIF data, for ECs that don't implement BIX
+    fn get_bif(&self) -> Result<battery::BifData>;
+
+    /// Get battery details beyond BIX that only some batteries report, such as the manufacture date
+    fn get_battery_extended(&self) -> Result<battery::BatteryExtended> {
+        Err(unsupported("Extended battery info not supported by this source"))
+    }
+
+    /// Get whether the system is running from AC or battery - see _PSR
+    fn get_power_source(&self) -> Result<battery::PowerSource>;
+
     /// Set battery trippoint
     fn set_btp(&self, trippoint: u32) -> Result<()>;
+
+    /// Get battery trippoint as currently configured in the EC
+    fn get_btp(&self) -> Result<u32>;
+
+    /// Get the current the charger is limited to, in mA
+    fn get_charge_current_limit(&self) -> Result<u32>;
+
+    /// Limit the current the charger may charge the battery at, in mA
+    fn set_charge_current_limit(&self, limit_ma: u32) -> Result<()>;
+
+    /// Read an arbitrary thermal variable by GUID - see GET_VAR
+    fn get_var(&self, guid: uuid::Uuid) -> Result<u32>;
+
+    /// Write an arbitrary thermal variable by GUID - see SET_VAR
+    fn set_var(&self, guid: uuid::Uuid, value: u32) -> Result<()>;
+
+    /// Read a signed thermal variable by GUID, e.g. an offset, which GET_VAR returns as two's complement
+    fn get_var_signed(&self, guid: uuid::Uuid) -> Result<i32> {
+        self.get_var(guid).map(|value| value as i32)
+    }
+
+    /// Write a signed thermal variable by GUID, which SET_VAR takes as two's complement
+    fn set_var_signed(&self, guid: uuid::Uuid, value: i32) -> Result<()> {
+        self.set_var(guid, value as u32)
+    }
+
+    /// Get EC firmware version
+    fn get_firmware_version(&self) -> Result<String>;
+
+    /// Subscribe to asynchronous EC events, `None` if the source can't deliver them
+    fn subscribe_ec_events(&self) -> Option<std::sync::mpsc::Receiver<events::EcEvent>> {
+        None
+    }
+
+    /// Whether `error` means the read isn't implemented rather than that it failed, see [`Unsupported`]
+    fn is_unsupported(&self, error: &Report) -> bool {
+        error.downcast_ref::<Unsupported>().is_some()
+    }
+
+    /// Whether writes are disabled, so modules can grey out their inputs
+    fn is_read_only(&self) -> bool {
+        false
+    }
 }
 
 pub trait RtcSource: Clone {
@@ -63,11 +176,51 @@ pub trait RtcSource: Clone {
 
     /// Get the timer value - see _TIV
     fn get_timer_value(&self, timer_id: AcpiTimerId) -> Result<AlarmTimerSeconds>;
+
+    /// Arm the timer to expire after the given seconds, or disarm it with `AlarmTimerSeconds::DISABLED` - see _STV
+    fn set_timer_value(&self, timer_id: AcpiTimerId, seconds: AlarmTimerSeconds) -> Result<()>;
+
+    /// Set how long after expiring the timer waits to wake the system - see _STP
+    fn set_expired_timer_wake_policy(&self, timer_id: AcpiTimerId, policy: AlarmExpiredWakePolicy) -> Result<()>;
+}
+
+pub trait UcsiSource: Clone {
+    /// Get the number of USB-C connectors managed by the PPM
+    fn get_connector_count(&self) -> Result<u8>;
+
+    /// Get the alternate modes (SVID and mode VDO) supported on a connector
+    fn get_alternate_modes(&self, connector: u8) -> Result<Vec<ucsi::AltMode>>;
+
+    /// Get connection state and data role of a connector
+    fn get_connector_status(&self, connector: u8) -> Result<ucsi::ConnectorStatus>;
+
+    /// Get the Request Data Object of the PD contract negotiated on a connector
+    fn get_current_rdo(&self, connector: u8) -> Result<ucsi::Rdo>;
+
+    /// Get the properties of the cable attached to a connector, see GET_CABLE_PROPERTY
+    fn get_cable_property(&self, connector: u8) -> Result<ucsi::CableProperty>;
+
+    /// Reset a connector, either a soft reset or a hard reset
+    fn connector_reset(&self, connector: u8, hard: bool) -> Result<()>;
+
+    /// Request a data role swap on a connector
+    fn swap_data_role(&self, connector: u8) -> Result<()>;
+
+    /// Get the reason the last command on a connector failed, see GET_ERROR_STATUS
+    fn get_error_status(&self, connector: u8) -> Result<ucsi::ErrorStatus>;
+
+    /// Subscribe to connector change notifications, `None` if the source can't deliver them
+    fn subscribe_events(&self) -> Option<std::sync::mpsc::Receiver<ucsi::UcsiEvent>> {
+        None
+    }
 }
 
+#[derive(Debug, Clone, Copy)]
 pub enum Threshold {
     /// On threshold temperature
     On,
+    /// Off threshold temperature, below On so a running fan doesn't stop and start around the On temperature
+    Off,
     /// Ramping threshold temperature
     Ramping,
     /// Max threshold temperature
@@ -0,0 +1,26 @@
+use color_eyre::Result;
+use std::{fs, path::Path};
+
+// Roll the previous log over once it gets this big so long sessions don't grow without bound
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+
+/// Send application log records to a file, keeping one rotated copy of the previous log
+pub fn init(path: &Path) -> Result<()> {
+    if let Ok(metadata) = fs::metadata(path)
+        && metadata.len() > MAX_LOG_SIZE
+    {
+        let mut rotated = path.as_os_str().to_owned();
+        rotated.push(".1");
+        fs::rename(path, rotated)?;
+    }
+
+    let file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    env_logger::Builder::new()
+        .filter_level(log::LevelFilter::Info)
+        .parse_default_env()
+        .target(env_logger::Target::Pipe(Box::new(file)))
+        .try_init()?;
+
+    log::info!("{} {} started", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    Ok(())
+}
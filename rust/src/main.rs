@@ -1,8 +1,27 @@
 use color_eyre::Result;
 use ec_demo::app::App;
+use ec_demo::cli::Cli;
+use ec_demo::dump::Dump;
 
 fn main() -> Result<()> {
     color_eyre::install()?;
+
+    // Parse before taking over the terminal so --help and errors print normally
+    let cli = Cli::parse();
+    if let Some(path) = &cli.log_file {
+        ec_demo::logging::init(path)?;
+    }
+    if let Some(path) = &cli.guid_map {
+        ec_demo::common::guid::load_map(path)?;
+    }
+
+    let baseline = cli.compare.as_deref().map(Dump::load).transpose()?;
+
+    if let Some(path) = &cli.from_dump {
+        let source = Dump::load(path)?;
+        return new_app(source, cli, baseline).run(ratatui::init());
+    }
+
     let terminal = ratatui::init();
 
     #[cfg(not(feature = "mock"))]
@@ -11,5 +30,20 @@ fn main() -> Result<()> {
     #[cfg(feature = "mock")]
     let source = ec_demo::mock::Mock::default();
 
-    App::new(source).run(terminal)
+    new_app(source, cli, baseline).run(terminal)
+}
+
+// The app, with a comparison tab when there's a baseline dump
+fn new_app<S: ec_demo::Source + Send + 'static>(source: S, cli: Cli, baseline: Option<Dump>) -> App<S> {
+    let baseline_name = cli
+        .compare
+        .as_deref()
+        .and_then(|path| path.file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let app = App::new(source, cli);
+    match baseline {
+        Some(baseline) => app.with_baseline(baseline, baseline_name),
+        None => app,
+    }
 }
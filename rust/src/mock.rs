@@ -1,18 +1,71 @@
-use crate::{RtcSource, Source, Threshold, common};
+use crate::events::{self, EcEvent};
+use crate::{RtcSource, Source, Threshold, UcsiSource, common::guid, ucsi};
 use color_eyre::Result;
 use embedded_mcu_hal::time::{Datetime, Month, UncheckedDatetime};
+use std::collections::BTreeMap;
 use std::sync::{
-    Mutex, OnceLock,
+    Mutex, Once, OnceLock,
     atomic::Ordering,
     atomic::{AtomicI64, AtomicU32},
+    mpsc::{self, Receiver, Sender},
 };
+use std::time::Instant;
 use time_alarm_service_messages::{
     AcpiDaylightSavingsTimeStatus, AcpiTimeZone, AcpiTimeZoneOffset, AcpiTimerId, AcpiTimestamp,
     AlarmExpiredWakePolicy, AlarmTimerSeconds, TimeAlarmDeviceCapabilities, TimerStatus,
 };
 
 static SET_RPM: AtomicI64 = AtomicI64::new(-1);
-static SAMPLE: OnceLock<Mutex<(i64, i64)>> = OnceLock::new();
+static BTP: AtomicU32 = AtomicU32::new(0);
+// mA, low enough limits hold the charge rate below its usual value
+static CHARGE_CURRENT_LIMIT: AtomicU32 = AtomicU32::new(3000);
+static SAMPLE_PERIOD: AtomicU32 = AtomicU32::new(100);
+// Raw _BST state, the charger is plugged in while charging
+static CHARGE_STATE: AtomicU32 = AtomicU32::new(2);
+// Bit per connector, set when the connector is acting as DFP
+static UCSI_DFP: AtomicU32 = AtomicU32::new(0);
+// Bit per connector, set when a partner is attached
+static UCSI_CONNECTED: AtomicU32 = AtomicU32::new(0b01);
+static THERMAL: OnceLock<Mutex<ThermalModel>> = OnceLock::new();
+// Variables written through SET_VAR that don't map onto another mock value
+static VARS: OnceLock<Mutex<BTreeMap<uuid::Uuid, u32>>> = OnceLock::new();
+// Subscribers to each kind of notification, dropped once their receiver is
+static EC_EVENT_SUBSCRIBERS: Mutex<Vec<Sender<EcEvent>>> = Mutex::new(Vec::new());
+static UCSI_EVENT_SUBSCRIBERS: Mutex<Vec<Sender<ucsi::UcsiEvent>>> = Mutex::new(Vec::new());
+
+// Send a notification to every live subscriber
+fn publish<T: Copy>(subscribers: &Mutex<Vec<Sender<T>>>, event: T) {
+    subscribers.lock().unwrap().retain(|tx| tx.send(event).is_ok());
+}
+
+fn raise_ec_event(id: u32) {
+    publish(&EC_EVENT_SUBSCRIBERS, EcEvent::new(id));
+}
+
+// Simulate a partner being plugged into and unplugged from connector 1 every so often, shared by every subscriber
+// so the connector doesn't toggle once per subscription
+fn start_connector_events() {
+    static STARTED: Once = Once::new();
+    STARTED.call_once(|| {
+        const CONNECTOR: u8 = 1;
+        std::thread::spawn(|| {
+            loop {
+                std::thread::sleep(std::time::Duration::from_secs(15));
+                UCSI_CONNECTED.fetch_xor(1 << CONNECTOR, Ordering::Relaxed);
+                publish(&UCSI_EVENT_SUBSCRIBERS, ucsi::UcsiEvent { connector: CONNECTOR });
+                raise_ec_event(events::id::UCSI_CONNECTOR_CHANGE);
+            }
+        });
+    });
+}
+
+// New subscription to notifications of one kind
+fn subscribe<T>(subscribers: &Mutex<Vec<Sender<T>>>) -> Receiver<T> {
+    let (tx, rx) = mpsc::channel();
+    subscribers.lock().unwrap().push(tx);
+    start_connector_events();
+    rx
+}
 
 #[derive(Default, Copy, Clone)]
 pub struct Mock {
@@ -25,43 +78,141 @@ impl Mock {
     }
 }
 
-impl Source for Mock {
-    fn get_temperature(&self) -> Result<f64> {
-        let mut sample = SAMPLE.get_or_init(|| Mutex::new((2732, 1))).lock().unwrap();
+// Simple first-order thermal model so the fan visibly affects temperature
+// A varying heat load warms the sensor, and cooling towards ambient is stronger the faster the fan spins.
+struct ThermalModel {
+    temp: f64,
+    // Tach reading, which follows the commanded speed with a lag like a real fan spinning up and down
+    rpm: f64,
+    // Latched between the On and Off temperatures, like firmware with fan hysteresis
+    fan_on: bool,
+    // Seconds simulated so far, drives the heat load
+    elapsed: f64,
+    last: Instant,
+}
+
+impl ThermalModel {
+    const AMBIENT: f64 = 25.0;
+    // Heat load in °C/s, swinging around the base over LOAD_PERIOD seconds
+    const LOAD_BASE: f64 = 0.4;
+    const LOAD_SWING: f64 = 0.2;
+    const LOAD_PERIOD: f64 = 120.0;
+    // Fraction of the excess over ambient lost per second with the fan off, and extra at full speed
+    const PASSIVE_COOLING: f64 = 0.01;
+    const FAN_COOLING: f64 = 0.04;
+    // Seconds for the fan to cover most (63%) of the way to a new commanded speed
+    const FAN_TIME_CONSTANT: f64 = 3.0;
 
-        sample.0 += 10 * sample.1;
-        if sample.0 >= 3232 || sample.0 <= 2732 {
-            sample.1 *= -1;
+    fn new() -> Self {
+        Self {
+            temp: Self::AMBIENT,
+            rpm: 0.0,
+            fan_on: false,
+            elapsed: 0.0,
+            last: Instant::now(),
         }
+    }
 
-        Ok(common::dk_to_c(sample.0 as u32))
+    // Advance the model to now and return the temperature
+    // Both the thermal and overview tabs read it, so time comes from the clock rather than the number of calls.
+    fn step(&mut self) -> f64 {
+        let mut dt = self.last.elapsed().as_secs_f64();
+        self.last = Instant::now();
+
+        // Integrate in steps of at most a second to keep it stable after a long pause
+        while dt > 0.0 {
+            let h = dt.min(1.0);
+            let load = Self::LOAD_BASE
+                + Self::LOAD_SWING * (2.0 * std::f64::consts::PI * self.elapsed / Self::LOAD_PERIOD).sin();
+            self.rpm += (self.fan_rpm() - self.rpm) * (h / Self::FAN_TIME_CONSTANT).min(1.0);
+            let fan = self.rpm / MAX_RPM;
+            let cooling = (Self::PASSIVE_COOLING + Self::FAN_COOLING * fan) * (self.temp - Self::AMBIENT);
+            self.temp += (load - cooling) * h;
+            self.elapsed += h;
+            dt -= h;
+        }
+        self.temp
     }
 
-    fn get_rpm(&self) -> Result<f64> {
-        use std::f64::consts::PI;
-        use std::sync::{Mutex, OnceLock};
+    // Fan speed at the current temperature, either the user's override or the automatic curve an EC would follow:
+    // off until On and back below Off, minimum speed up to Ramping, then ramping linearly to full speed at Max
+    fn fan_rpm(&mut self) -> f64 {
+        let was_on = self.fan_on;
+        if self.temp >= ON_TEMP {
+            self.fan_on = true;
+        } else if self.temp < OFF_TEMP {
+            self.fan_on = false;
+        }
+        if self.fan_on != was_on {
+            raise_ec_event(events::id::THERMAL_THRESHOLD);
+        }
 
-        // For mock, if user sets RPM, we just always return what was last set instead of sin wave
         let set_rpm = SET_RPM.load(Ordering::Relaxed);
         if set_rpm >= 0 {
-            Ok(set_rpm as f64)
+            set_rpm as f64
+        } else if !self.fan_on {
+            0.0
+        } else if self.temp < RAMP_TEMP {
+            MIN_ON_RPM
         } else {
-            // Generate sin wave
-            static SAMPLE: OnceLock<Mutex<f64>> = OnceLock::new();
-            let mut sample = SAMPLE.get_or_init(|| Mutex::new(0.0)).lock().unwrap();
-
-            let freq = 0.1;
-            let amplitude = 3000.0;
-            let base = 3000.0;
-            let rpm = (sample.sin() * amplitude) + base;
-
-            *sample += freq;
-            if *sample > 2.0 * PI {
-                *sample -= 2.0 * PI;
-            }
+            let ramp = ((self.temp - RAMP_TEMP) / (MAX_TEMP - RAMP_TEMP)).min(1.0);
+            MIN_ON_RPM + ramp * (MAX_RPM - MIN_ON_RPM)
+        }
+    }
+}
 
-            Ok(rpm)
+fn thermal_model() -> std::sync::MutexGuard<'static, ThermalModel> {
+    THERMAL.get_or_init(|| Mutex::new(ThermalModel::new())).lock().unwrap()
+}
+
+// Extra fans follow the first, each swinging around it by up to FAN_SPREAD with its own phase
+const FAN_COUNT: u8 = 3;
+const FAN_SPREAD: f64 = 0.1;
+const FAN_SPREAD_PERIOD: f64 = 20.0;
+const MIN_ON_RPM: f64 = 2000.0;
+const MAX_RPM: f64 = 6000.0;
+// Off sits just above where minimum fan speed settles under light load, so the fan visibly cycles with the load
+const ON_TEMP: f64 = 39.0;
+const OFF_TEMP: f64 = 38.0;
+const RAMP_TEMP: f64 = 40.0;
+const MAX_TEMP: f64 = 44.0;
+
+impl Source for Mock {
+    fn get_temperature(&self) -> Result<f64> {
+        Ok(thermal_model().step())
+    }
+
+    fn get_rpm(&self) -> Result<f64> {
+        let mut model = thermal_model();
+        model.step();
+        Ok(model.rpm)
+    }
+
+    fn get_fan_count(&self) -> Result<u8> {
+        Ok(FAN_COUNT)
+    }
+
+    fn get_fan_rpm(&self, fan: u8) -> Result<f64> {
+        if fan >= FAN_COUNT {
+            return Err(color_eyre::eyre::eyre!("No fan {fan}"));
         }
+        let mut model = thermal_model();
+        model.step();
+        let phase = 2.0 * std::f64::consts::PI * f64::from(fan) / f64::from(FAN_COUNT);
+        let angle = 2.0 * std::f64::consts::PI * model.elapsed / FAN_SPREAD_PERIOD + phase;
+        // The first fan is the modelled one, the others vary around it
+        let spread = if fan == 0 { 0.0 } else { FAN_SPREAD * angle.sin() };
+        Ok(model.rpm * (1.0 + spread))
+    }
+
+    fn get_commanded_rpm(&self) -> Result<f64> {
+        let mut model = thermal_model();
+        model.step();
+        Ok(model.fan_rpm())
+    }
+
+    fn has_commanded_rpm(&self) -> bool {
+        true
     }
 
     fn get_min_rpm(&self) -> Result<f64> {
@@ -69,52 +220,88 @@ impl Source for Mock {
     }
 
     fn get_max_rpm(&self) -> Result<f64> {
-        Ok(6000.0)
+        Ok(MAX_RPM)
     }
 
     fn get_threshold(&self, threshold: Threshold) -> Result<f64> {
         match threshold {
-            Threshold::On => Ok(28.0),
-            Threshold::Ramping => Ok(40.0),
-            Threshold::Max => Ok(44.0),
+            Threshold::On => Ok(ON_TEMP),
+            Threshold::Off => Ok(OFF_TEMP),
+            Threshold::Ramping => Ok(RAMP_TEMP),
+            Threshold::Max => Ok(MAX_TEMP),
         }
     }
 
+    fn get_sample_period(&self) -> Result<u32> {
+        Ok(SAMPLE_PERIOD.load(Ordering::Relaxed))
+    }
+
+    fn set_sample_period(&self, period_ms: u32) -> Result<()> {
+        SAMPLE_PERIOD.store(period_ms, Ordering::Relaxed);
+        Ok(())
+    }
+
     fn set_rpm(&self, rpm: f64) -> Result<()> {
         SET_RPM.store(rpm as i64, Ordering::Relaxed);
         Ok(())
     }
 
+    fn clear_rpm_override(&self) -> Result<()> {
+        SET_RPM.store(-1, Ordering::Relaxed);
+        Ok(())
+    }
+
     fn get_bst(&self) -> Result<crate::battery::BstData> {
-        static STATE: AtomicU32 = AtomicU32::new(2);
         const MAX_CAPACITY: u32 = 10000;
         static CAPACITY: AtomicU32 = AtomicU32::new(0);
         const RATE: u32 = 1000;
+        const CHARGE_RATE: u32 = 3839;
+        const CRITICAL_CAPACITY: u32 = 2000;
 
-        let state = STATE.load(Ordering::Relaxed);
+        let state = CHARGE_STATE.load(Ordering::Relaxed);
         let capacity = CAPACITY.load(Ordering::Relaxed);
+        let voltage = 11000 + capacity / 5;
         let mut new_capacity = capacity;
 
+        // Charging power in mW is capped by the current limit at the present voltage
+        let rate = if state == 2 {
+            let limit = CHARGE_CURRENT_LIMIT.load(Ordering::Relaxed);
+            CHARGE_RATE.min(limit.saturating_mul(voltage) / 1000)
+        } else {
+            CHARGE_RATE
+        };
+
         // We are only using atomics to satisfy borrow-checker
         // Thus we update non-atomically for simplicity
         if state == 2 {
-            new_capacity += RATE;
+            new_capacity += RATE * rate / CHARGE_RATE;
             if new_capacity > MAX_CAPACITY {
-                STATE.store(1, Ordering::Relaxed);
+                CHARGE_STATE.store(1, Ordering::Relaxed);
+                raise_ec_event(events::id::BATTERY_STATUS);
+                raise_ec_event(events::id::POWER_SOURCE);
             }
         } else {
             new_capacity -= RATE;
             if new_capacity < RATE {
-                STATE.store(2, Ordering::Relaxed);
+                CHARGE_STATE.store(2, Ordering::Relaxed);
+                raise_ec_event(events::id::BATTERY_STATUS);
+                raise_ec_event(events::id::POWER_SOURCE);
             }
         }
         CAPACITY.store(new_capacity.clamp(0, MAX_CAPACITY), Ordering::Relaxed);
 
+        // Flag critical energy state when running low on battery
+        let state = if state == 1 && capacity < CRITICAL_CAPACITY {
+            state | 0x4
+        } else {
+            state
+        };
+
         Ok(crate::battery::BstData {
-            state: crate::battery::ChargeState::try_from(state)?,
-            rate: 3839,
+            state: crate::battery::ChargeState::from_bits(state),
+            rate,
             capacity,
-            voltage: 12569,
+            voltage,
         })
     }
 
@@ -144,16 +331,99 @@ impl Source for Mock {
         })
     }
 
-    fn set_btp(&self, _trippoint: u32) -> Result<()> {
-        // Do nothing for mock
+    fn get_bif(&self) -> Result<crate::battery::BifData> {
+        // Same battery as BIX, minus the fields legacy firmware doesn't report
+        let bix = self.get_bix()?;
+        Ok(crate::battery::BifData {
+            power_unit: bix.power_unit,
+            design_capacity: bix.design_capacity,
+            last_full_capacity: bix.last_full_capacity,
+            battery_technology: bix.battery_technology,
+            design_voltage: bix.design_voltage,
+            warning_capacity: bix.warning_capacity,
+            low_capacity: bix.low_capacity,
+            capacity_gran1: bix.capacity_gran1,
+            capacity_gran2: bix.capacity_gran2,
+            model_number: bix.model_number,
+            serial_number: bix.serial_number,
+            battery_type: bix.battery_type,
+            oem_info: bix.oem_info,
+        })
+    }
+
+    fn get_battery_extended(&self) -> Result<crate::battery::BatteryExtended> {
+        Ok(crate::battery::BatteryExtended {
+            manufacture_date: chrono::NaiveDate::from_ymd_opt(2024, 3, 15),
+            serial_extended: Some("123-45-678 lot A7".to_string()),
+            rate_capacities: vec![(5, 9890), (2, 9520), (1, 9110)],
+        })
+    }
+
+    fn get_power_source(&self) -> Result<crate::battery::PowerSource> {
+        Ok(if CHARGE_STATE.load(Ordering::Relaxed) == 2 {
+            crate::battery::PowerSource::Ac
+        } else {
+            crate::battery::PowerSource::Battery
+        })
+    }
+
+    fn set_btp(&self, trippoint: u32) -> Result<()> {
+        BTP.store(trippoint, Ordering::Relaxed);
         Ok(())
     }
+
+    fn get_btp(&self) -> Result<u32> {
+        Ok(BTP.load(Ordering::Relaxed))
+    }
+
+    fn get_charge_current_limit(&self) -> Result<u32> {
+        Ok(CHARGE_CURRENT_LIMIT.load(Ordering::Relaxed))
+    }
+
+    fn set_charge_current_limit(&self, limit_ma: u32) -> Result<()> {
+        CHARGE_CURRENT_LIMIT.store(limit_ma, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn get_var(&self, guid: uuid::Uuid) -> Result<u32> {
+        match guid {
+            guid::FAN_CURRENT_RPM => Ok(self.get_rpm()? as u32),
+            guid::FAN_MIN_RPM => Ok(self.get_min_rpm()? as u32),
+            guid::FAN_MAX_RPM => Ok(self.get_max_rpm()? as u32),
+            guid::SENSOR_SAMPLE_PERIOD => self.get_sample_period(),
+            _ => VARS
+                .get_or_init(Default::default)
+                .lock()
+                .unwrap()
+                .get(&guid)
+                .copied()
+                .ok_or_else(|| color_eyre::eyre::eyre!("GET_VAR({guid}) unknown variable")),
+        }
+    }
+
+    fn set_var(&self, guid: uuid::Uuid, value: u32) -> Result<()> {
+        match guid {
+            guid::FAN_CURRENT_RPM => self.set_rpm(value.into()),
+            guid::SENSOR_SAMPLE_PERIOD => self.set_sample_period(value),
+            _ => {
+                VARS.get_or_init(Default::default).lock().unwrap().insert(guid, value);
+                Ok(())
+            }
+        }
+    }
+
+    fn get_firmware_version(&self) -> Result<String> {
+        Ok("0.2.0-mock".to_string())
+    }
+
+    fn subscribe_ec_events(&self) -> Option<Receiver<EcEvent>> {
+        Some(subscribe(&EC_EVENT_SUBSCRIBERS))
+    }
 }
 
 #[derive(Copy, Clone)]
 struct MockRtc {
     time: AcpiTimestamp,
-    timers: [MockRtcTimer; 2],
 }
 
 #[derive(Copy, Clone)]
@@ -163,14 +433,19 @@ struct MockRtcTimer {
     timer_status: TimerStatus,
 }
 
-impl Default for MockRtcTimer {
-    fn default() -> Self {
-        Self {
-            value: AlarmTimerSeconds(0),
-            wake_policy: AlarmExpiredWakePolicy::INSTANTLY,
-            timer_status: TimerStatus(0),
-        }
-    }
+impl MockRtcTimer {
+    const INITIAL: Self = Self {
+        value: AlarmTimerSeconds(0),
+        wake_policy: AlarmExpiredWakePolicy::INSTANTLY,
+        timer_status: TimerStatus(0),
+    };
+}
+
+// Shared rather than part of MockRtc, so values set through one copy of the source are seen by all
+static RTC_TIMERS: Mutex<[MockRtcTimer; 2]> = Mutex::new([MockRtcTimer::INITIAL; 2]);
+
+fn with_timer<T>(timer_id: AcpiTimerId, f: impl FnOnce(&mut MockRtcTimer) -> T) -> T {
+    f(&mut RTC_TIMERS.lock().unwrap()[timer_id as usize])
 }
 
 impl MockRtc {
@@ -189,13 +464,8 @@ impl MockRtc {
                 ),
                 dst_status: AcpiDaylightSavingsTimeStatus::NotObserved,
             },
-            timers: [MockRtcTimer::default(); 2],
         }
     }
-
-    fn get_timer(&self, timer_id: AcpiTimerId) -> &MockRtcTimer {
-        &self.timers[timer_id as usize]
-    }
 }
 
 impl Default for MockRtc {
@@ -214,14 +484,103 @@ impl RtcSource for Mock {
     }
 
     fn get_wake_status(&self, timer_id: AcpiTimerId) -> Result<TimerStatus> {
-        Ok(self.rtc.get_timer(timer_id).timer_status)
+        Ok(with_timer(timer_id, |timer| timer.timer_status))
     }
 
     fn get_expired_timer_wake_policy(&self, timer_id: AcpiTimerId) -> Result<AlarmExpiredWakePolicy> {
-        Ok(self.rtc.get_timer(timer_id).wake_policy)
+        Ok(with_timer(timer_id, |timer| timer.wake_policy))
     }
 
     fn get_timer_value(&self, timer_id: AcpiTimerId) -> Result<AlarmTimerSeconds> {
-        Ok(self.rtc.get_timer(timer_id).value)
+        Ok(with_timer(timer_id, |timer| timer.value))
+    }
+
+    fn set_timer_value(&self, timer_id: AcpiTimerId, seconds: AlarmTimerSeconds) -> Result<()> {
+        with_timer(timer_id, |timer| timer.value = seconds);
+        Ok(())
+    }
+
+    fn set_expired_timer_wake_policy(&self, timer_id: AcpiTimerId, policy: AlarmExpiredWakePolicy) -> Result<()> {
+        with_timer(timer_id, |timer| timer.wake_policy = policy);
+        Ok(())
+    }
+}
+
+impl UcsiSource for Mock {
+    fn get_connector_count(&self) -> Result<u8> {
+        Ok(2)
+    }
+
+    fn get_alternate_modes(&self, connector: u8) -> Result<Vec<ucsi::AltMode>> {
+        match connector {
+            0 => Ok(vec![
+                ucsi::AltMode {
+                    svid: ucsi::SVID_DISPLAYPORT,
+                    vdo: 0x0000_0c05,
+                },
+                ucsi::AltMode {
+                    svid: ucsi::SVID_THUNDERBOLT,
+                    vdo: 0x0000_0001,
+                },
+            ]),
+            1 => Ok(vec![]),
+            _ => Err(color_eyre::eyre::eyre!("Invalid connector {connector}")),
+        }
+    }
+
+    fn get_connector_status(&self, connector: u8) -> Result<ucsi::ConnectorStatus> {
+        let dfp = UCSI_DFP.load(Ordering::Relaxed) & (1 << connector) != 0;
+        let connected = UCSI_CONNECTED.load(Ordering::Relaxed) & (1 << connector) != 0;
+        // Sinking from the 9 V charger described by get_current_rdo
+        Ok(ucsi::ConnectorStatus {
+            connected,
+            data_role: if dfp { ucsi::DataRole::Dfp } else { ucsi::DataRole::Ufp },
+            power_role: Some(ucsi::PowerRole::Sink),
+            voltage_mv: connected.then_some(9000),
+        })
+    }
+
+    fn get_current_rdo(&self, _connector: u8) -> Result<ucsi::Rdo> {
+        // 9 V is PDO 2 of a typical 5/9/15/20 V charger, requested at 3 A and USB communications capable
+        Ok(ucsi::Rdo((2 << 28) | (1 << 25) | (300 << 10) | 300))
+    }
+
+    fn get_cable_property(&self, connector: u8) -> Result<ucsi::CableProperty> {
+        // An e-marked 5 A cable on connector 0, and a plain one that falls back to the 3 A default elsewhere
+        Ok(if connector == 0 {
+            ucsi::CableProperty {
+                e_marked: true,
+                max_current_ma: 5000,
+                max_voltage_mv: 20000,
+                active: false,
+            }
+        } else {
+            ucsi::CableProperty {
+                e_marked: false,
+                max_current_ma: 3000,
+                max_voltage_mv: 20000,
+                active: false,
+            }
+        })
+    }
+
+    fn connector_reset(&self, connector: u8, _hard: bool) -> Result<()> {
+        // Reset drops the connector back to its default UFP role
+        UCSI_DFP.fetch_and(!(1 << connector), Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn swap_data_role(&self, connector: u8) -> Result<()> {
+        UCSI_DFP.fetch_xor(1 << connector, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn get_error_status(&self, _connector: u8) -> Result<ucsi::ErrorStatus> {
+        // Mock commands never fail
+        Ok(ucsi::ErrorStatus::default())
+    }
+
+    fn subscribe_events(&self) -> Option<Receiver<ucsi::UcsiEvent>> {
+        Some(subscribe(&UCSI_EVENT_SUBSCRIBERS))
     }
 }
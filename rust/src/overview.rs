@@ -0,0 +1,198 @@
+use crate::Source;
+use crate::app::Module;
+use crate::battery::{self, BstData, PowerSource};
+use crate::common;
+use crate::rtc;
+use color_eyre::{Result, eyre::eyre};
+use crossterm::event::Event;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Stylize, palette::tailwind},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Widget},
+};
+use time_alarm_service_messages::AcpiTimestamp;
+
+const LABEL_COLOR: Color = tailwind::SLATE.c200;
+const DATA_NOT_YET_RETRIEVED_MSG: &str = "Data not yet retrieved";
+const NO_DATA: &str = "—";
+
+/// One-glance summary of every subsystem
+///
+/// Reads go through the proxy's per-tick cache, so the values the other tabs already read this tick cost no extra
+/// EC traffic.
+pub struct Overview<S: Source> {
+    source: S,
+    temperature: Result<f64>,
+    rpm: Result<f64>,
+    bst: Result<BstData>,
+    power_source: Result<PowerSource>,
+    last_full_capacity: Result<u32>,
+    time: Result<AcpiTimestamp>,
+    connector_count: Result<u8>,
+}
+
+impl<S: Source> Module for Overview<S> {
+    fn title(&self) -> &'static str {
+        "Overview"
+    }
+
+    fn update(&mut self) {
+        self.temperature = self.source.get_temperature();
+        self.rpm = self.source.get_rpm();
+        self.bst = self.source.get_bst();
+        self.power_source = self.source.get_power_source();
+        self.time = self.source.get_real_time();
+
+        // These are static, so don't try to update after a successful fetch
+        common::retry_static(&mut self.last_full_capacity, || {
+            // Legacy ECs only have _BIF, which reports it too
+            self.source
+                .get_bix()
+                .map(|bix| bix.last_full_capacity)
+                .or_else(|_| self.source.get_bif().map(|bif| bif.last_full_capacity))
+        });
+        common::retry_static(&mut self.connector_count, || self.source.get_connector_count());
+    }
+
+    fn handle_event(&mut self, _evt: &Event) {}
+
+    fn copy_value(&self) -> Option<String> {
+        // One line summary of whatever we managed to read
+        let parts: Vec<String> = [
+            self.temperature
+                .as_ref()
+                .ok()
+                .map(|temp| format!("Temperature {temp:.1} °C")),
+            self.rpm.as_ref().ok().map(|rpm| format!("Fan {} RPM", rpm.round())),
+            self.battery_line().ok().map(|line| format!("Battery {line}")),
+            self.power_source
+                .as_ref()
+                .ok()
+                .map(|source| source.as_str().to_string()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        let [top, bottom] = Layout::vertical([Constraint::Ratio(1, 2); 2]).areas(area);
+        let [temp_area, fan_area, battery_area] = Layout::horizontal([Constraint::Ratio(1, 3); 3]).areas(top);
+        let [state_area, time_area, health_area] = Layout::horizontal([Constraint::Ratio(1, 3); 3]).areas(bottom);
+
+        render_tile(
+            "Temperature",
+            self.temperature.as_ref().map(|temp| format!("{temp:.1} °C").into()),
+            temp_area,
+            buf,
+        );
+        render_tile(
+            "Fan",
+            self.rpm.as_ref().map(|rpm| format!("{} RPM", rpm.round()).into()),
+            fan_area,
+            buf,
+        );
+        render_tile("Battery", self.battery_line(), battery_area, buf);
+        render_tile("Charge State", self.charge_state_line(), state_area, buf);
+        render_tile(
+            "RTC Time",
+            self.time
+                .as_ref()
+                .map(|timestamp| rtc::format_time(timestamp.datetime).into()),
+            time_area,
+            buf,
+        );
+        self.render_health(health_area, buf);
+    }
+
+    fn report(&self) -> String {
+        // Every other tab already reports these values in more detail
+        let (healthy, total) = self.health();
+        common::report_section("Health", true, [format!("{healthy}/{total} subsystems responding")])
+    }
+}
+
+impl<S: Source> Overview<S> {
+    pub fn new(source: S) -> Self {
+        let mut inst = Self {
+            source,
+            temperature: Err(eyre!(DATA_NOT_YET_RETRIEVED_MSG)),
+            rpm: Err(eyre!(DATA_NOT_YET_RETRIEVED_MSG)),
+            bst: Err(eyre!(DATA_NOT_YET_RETRIEVED_MSG)),
+            power_source: Err(eyre!(DATA_NOT_YET_RETRIEVED_MSG)),
+            last_full_capacity: Err(eyre!(DATA_NOT_YET_RETRIEVED_MSG)),
+            time: Err(eyre!(DATA_NOT_YET_RETRIEVED_MSG)),
+            connector_count: Err(eyre!(DATA_NOT_YET_RETRIEVED_MSG)),
+        };
+
+        inst.update();
+        inst
+    }
+
+    fn battery_line(&self) -> Result<Line<'static>, &color_eyre::Report> {
+        let bst = self.bst.as_ref()?;
+        let last_full = *self.last_full_capacity.as_ref().unwrap_or(&0);
+        Ok(match battery::state_of_charge(bst.capacity, last_full) {
+            Some(soc) => format!("{soc:.0}%").into(),
+            None => format!("{} remaining", bst.capacity).into(),
+        })
+    }
+
+    // Charge state, led by the power source when the EC reports it
+    fn charge_state_line(&self) -> Result<Line<'static>, &color_eyre::Report> {
+        let bst = self.bst.as_ref()?;
+        let mut spans = match &self.power_source {
+            Ok(source) => vec![Span::raw(format!("{} | ", source.as_str_with_icon()))],
+            Err(_) => vec![],
+        };
+        spans.extend(bst.state.as_spans());
+        Ok(Line::from(spans))
+    }
+
+    // Number of subsystem reads that succeeded, out of the total
+    fn health(&self) -> (usize, usize) {
+        let results = [
+            self.temperature.is_ok(),
+            self.rpm.is_ok(),
+            self.bst.is_ok(),
+            self.last_full_capacity.is_ok(),
+            self.time.is_ok(),
+            self.connector_count.is_ok(),
+        ];
+        (results.iter().filter(|&&ok| ok).count(), results.len())
+    }
+
+    fn render_health(&self, area: Rect, buf: &mut Buffer) {
+        let (healthy, total) = self.health();
+        let mut lines = vec![Line::from(format!("{healthy}/{total} responding")).bold()];
+        lines.push(match &self.connector_count {
+            Ok(count) => Line::raw(format!("{count} USB-C connectors")),
+            Err(_) => Line::raw(format!("USB-C {NO_DATA}")),
+        });
+
+        let block = tile_block("Health", healthy == total);
+        Paragraph::new(lines).centered().block(block).render(area, buf);
+    }
+}
+
+fn tile_block(title: &str, success: bool) -> Block<'static> {
+    Block::bordered()
+        .title(common::title_str_with_status(title, success))
+        .fg(LABEL_COLOR)
+}
+
+// A bordered tile with a single large value, or a dash if the read failed
+fn render_tile(title: &str, value: Result<Line<'static>, &color_eyre::Report>, area: Rect, buf: &mut Buffer) {
+    let block = tile_block(title, value.is_ok());
+    let line = value.unwrap_or_else(|_| Span::raw(NO_DATA).into()).bold();
+
+    // Vertically centre the value inside the tile
+    let inner = block.inner(area);
+    let [_, value_area, _] =
+        Layout::vertical([Constraint::Fill(1), Constraint::Length(1), Constraint::Fill(1)]).areas(inner);
+    block.render(area, buf);
+    Paragraph::new(line).centered().render(value_area, buf);
+}
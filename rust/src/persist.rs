@@ -0,0 +1,60 @@
+use std::{collections::BTreeMap, fs, path::PathBuf, str::FromStr};
+
+const STATE_DIR: &str = "ec_demo";
+const STATE_FILE: &str = "state.txt";
+
+/// Small `key=value` store used to remember UI state across restarts
+#[derive(Default)]
+pub struct SavedState {
+    values: BTreeMap<String, String>,
+}
+
+impl SavedState {
+    /// Load saved state, falling back to empty state if the file is missing or unreadable
+    pub fn load() -> Self {
+        let values = state_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| {
+                contents
+                    .lines()
+                    .filter_map(|line| line.split_once('='))
+                    .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { values }
+    }
+
+    /// Write state back to disk
+    pub fn save(&self) -> std::io::Result<()> {
+        let Some(path) = state_path() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let contents: String = self.values.iter().map(|(k, v)| format!("{k}={v}\n")).collect();
+        fs::write(path, contents)
+    }
+
+    /// Get a value, returning `None` if it is missing or fails to parse
+    pub fn get<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.values.get(key).and_then(|value| value.parse().ok())
+    }
+
+    pub fn set(&mut self, key: &str, value: impl ToString) {
+        self.values.insert(key.to_string(), value.to_string());
+    }
+}
+
+// Per-user state location: %LOCALAPPDATA% on Windows, XDG state dir elsewhere
+fn state_path() -> Option<PathBuf> {
+    let base = std::env::var_os("LOCALAPPDATA")
+        .or_else(|| std::env::var_os("XDG_STATE_HOME"))
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state")))?;
+
+    Some(base.join(STATE_DIR).join(STATE_FILE))
+}
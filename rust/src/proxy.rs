@@ -0,0 +1,459 @@
+use crate::{RtcSource, Source, Threshold, UcsiSource, battery, ucsi};
+use color_eyre::{Report, Result, eyre::eyre};
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    rc::Rc,
+    time::{Duration, Instant, SystemTime},
+};
+use time_alarm_service_messages::{
+    AcpiTimerId, AcpiTimestamp, AlarmExpiredWakePolicy, AlarmTimerSeconds, TimeAlarmDeviceCapabilities, TimerStatus,
+};
+
+// Number of calls kept in the transaction trace
+const MAX_TRANSACTIONS: usize = 100;
+
+// Consecutive failures of a read before it is paused, and how long the pause lasts, doubling up to the cap
+const BACKOFF_AFTER: u32 = 3;
+const BACKOFF_INITIAL: Duration = Duration::from_secs(2);
+const BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+// A read already made this tick, failures kept as their message
+type Cached = std::result::Result<Rc<dyn Any>, String>;
+
+// Failure count of a read that keeps failing, and when it may next be tried
+struct Backoff {
+    failures: u32,
+    retry_at: Instant,
+}
+
+/// A single call made through the proxy, kept for the transaction trace
+pub struct Transaction {
+    pub at: SystemTime,
+    pub operation: String,
+    /// `None` on success, otherwise the error message
+    pub error: Option<String>,
+    pub duration: Duration,
+}
+
+/// Wraps the real source so policy applying to every access (read-only mode, logging) lives in one place
+#[derive(Clone)]
+pub struct SourceProxy<S: Source> {
+    inner: S,
+    read_only: bool,
+    // Shared by every clone, so the trace covers calls from all modules
+    transactions: Rc<RefCell<VecDeque<Transaction>>>,
+    // Reads that are failing, by operation, shared so every module sees the same pause
+    backoff: Rc<RefCell<BTreeMap<String, Backoff>>>,
+    // Reads the source doesn't implement, with why, which are no longer sent
+    unsupported: Rc<RefCell<BTreeMap<String, String>>>,
+    // Reads that have worked at least once, so a later failure is never taken to mean unsupported
+    supported: Rc<RefCell<BTreeSet<String>>>,
+    // Results of the reads made this tick by operation, `None` outside a tick
+    cache: Rc<RefCell<Option<BTreeMap<String, Cached>>>>,
+}
+
+impl<S: Source> SourceProxy<S> {
+    pub fn new(inner: S, read_only: bool) -> Self {
+        Self {
+            inner,
+            read_only,
+            transactions: Default::default(),
+            backoff: Default::default(),
+            unsupported: Default::default(),
+            supported: Default::default(),
+            cache: Default::default(),
+        }
+    }
+
+    /// Start a tick, from here on each polled read goes to the source once and later calls get the same result
+    ///
+    /// This way tabs showing the same value, the recorder and the web snapshot don't each add EC traffic.
+    pub fn begin_tick(&self) {
+        *self.cache.borrow_mut() = Some(BTreeMap::new());
+    }
+
+    /// End the tick, so the next read goes to the source again
+    pub fn end_tick(&self) {
+        *self.cache.borrow_mut() = None;
+    }
+
+    /// Reads currently paused after failing repeatedly, with how long until they are retried
+    pub fn paused_reads(&self) -> Vec<(String, Duration)> {
+        let now = Instant::now();
+        self.backoff
+            .borrow()
+            .iter()
+            .filter(|(_, backoff)| backoff.failures >= BACKOFF_AFTER && backoff.retry_at > now)
+            .map(|(operation, backoff)| (operation.clone(), backoff.retry_at - now))
+            .collect()
+    }
+
+    /// Retry every paused or unsupported read on its next call
+    pub fn clear_backoff(&self) {
+        self.backoff.borrow_mut().clear();
+        self.unsupported.borrow_mut().clear();
+    }
+
+    /// Most recent calls made through any clone of this proxy, oldest first
+    pub fn transactions(&self) -> std::cell::Ref<'_, VecDeque<Transaction>> {
+        self.transactions.borrow()
+    }
+
+    /// The wrapped source, for bulk reads such as benchmarks that would flush the transaction trace
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    // Every write goes through here so nothing slips past read-only mode, and every command is logged
+    fn write<T>(&self, operation: &str, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        // Whatever was read this tick may no longer hold
+        if let Some(cache) = self.cache.borrow_mut().as_mut() {
+            cache.clear();
+        }
+
+        let result = self.trace(operation, || {
+            if self.read_only {
+                Err(eyre!("{operation} blocked: read-only mode"))
+            } else {
+                f()
+            }
+        });
+
+        match &result {
+            Ok(_) => log::info!("{operation} succeeded"),
+            Err(e) => log::warn!("{operation} failed: {e}"),
+        }
+        result
+    }
+
+    // Polled reads are answered from the cache when one was already made this tick, see begin_tick
+    fn read<T: Clone + 'static>(&self, operation: &str, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        if let Some(cached) = self.cache.borrow().as_ref().and_then(|cache| cache.get(operation)) {
+            match cached {
+                Ok(value) => {
+                    if let Some(value) = value.downcast_ref::<T>() {
+                        return Ok(value.clone());
+                    }
+                }
+                Err(e) => return Err(eyre!("{e}")),
+            }
+        }
+
+        let result = self.read_uncached(operation, f);
+        if let Some(cache) = self.cache.borrow_mut().as_mut() {
+            let cached = match &result {
+                Ok(value) => Ok(Rc::new(value.clone()) as Rc<dyn Any>),
+                Err(e) => Err(e.to_string()),
+            };
+            cache.insert(operation.to_string(), cached);
+        }
+        result
+    }
+
+    // Polled reads back off when they keep failing, so a subsystem the EC hasn't brought up yet doesn't eat bus time
+    // Reads the source doesn't implement are dropped instead, since retrying them would never help
+    fn read_uncached<T>(&self, operation: &str, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        if let Some(reason) = self.unsupported.borrow().get(operation) {
+            return Err(crate::unsupported(reason.clone()));
+        }
+        if let Some(backoff) = self.backoff.borrow().get(operation)
+            && backoff.failures >= BACKOFF_AFTER
+            && backoff.retry_at > Instant::now()
+        {
+            return Err(eyre!(
+                "{operation} paused after {} failures, retrying in {} s",
+                backoff.failures,
+                (backoff.retry_at - Instant::now()).as_secs() + 1
+            ));
+        }
+
+        let result = self.read_once(operation, f);
+        if let Err(e) = &result
+            && self.inner.is_unsupported(e)
+            && !self.supported.borrow().contains(operation)
+        {
+            log::info!("{operation} not supported, no longer polled");
+            self.backoff.borrow_mut().remove(operation);
+            self.unsupported
+                .borrow_mut()
+                .insert(operation.to_string(), e.to_string());
+            return result;
+        }
+
+        let mut backoff = self.backoff.borrow_mut();
+        if result.is_ok() {
+            if !self.supported.borrow().contains(operation) {
+                self.supported.borrow_mut().insert(operation.to_string());
+            }
+            if backoff
+                .remove(operation)
+                .is_some_and(|backoff| backoff.failures >= BACKOFF_AFTER)
+            {
+                log::info!("{operation} recovered");
+            }
+        } else {
+            let entry = backoff.entry(operation.to_string()).or_insert(Backoff {
+                failures: 0,
+                retry_at: Instant::now(),
+            });
+            entry.failures += 1;
+            if entry.failures >= BACKOFF_AFTER {
+                let delay = BACKOFF_INITIAL
+                    .saturating_mul(1 << (entry.failures - BACKOFF_AFTER).min(16))
+                    .min(BACKOFF_MAX);
+                entry.retry_at = Instant::now() + delay;
+                log::warn!(
+                    "{operation} paused for {} s after {} failures",
+                    delay.as_secs(),
+                    entry.failures
+                );
+            }
+        }
+        result
+    }
+
+    // Reads made on request rather than polled, which are always sent
+    fn read_once<T>(&self, operation: &str, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        let result = self.trace(operation, f);
+        if let Err(e) = &result {
+            log::warn!("{operation} failed: {e}");
+        }
+        result
+    }
+
+    // Time the call and record it in the transaction trace
+    fn trace<T>(&self, operation: &str, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        let at = SystemTime::now();
+        let started = Instant::now();
+        let result = f();
+
+        let mut transactions = self.transactions.borrow_mut();
+        transactions.push_back(Transaction {
+            at,
+            operation: operation.to_string(),
+            error: result.as_ref().err().map(ToString::to_string),
+            duration: started.elapsed(),
+        });
+        if transactions.len() > MAX_TRANSACTIONS {
+            transactions.pop_front();
+        }
+        result
+    }
+}
+
+impl<S: Source> Source for SourceProxy<S> {
+    fn get_temperature(&self) -> Result<f64> {
+        self.read("GET_TMP", || self.inner.get_temperature())
+    }
+
+    fn get_rpm(&self) -> Result<f64> {
+        self.read("GET_RPM", || self.inner.get_rpm())
+    }
+
+    fn get_fan_count(&self) -> Result<u8> {
+        self.read("GET_FAN_COUNT", || self.inner.get_fan_count())
+    }
+
+    fn get_fan_rpm(&self, fan: u8) -> Result<f64> {
+        self.read(&format!("GET_FAN_RPM({fan})"), || self.inner.get_fan_rpm(fan))
+    }
+
+    fn get_commanded_rpm(&self) -> Result<f64> {
+        self.read("GET_COMMANDED_RPM", || self.inner.get_commanded_rpm())
+    }
+
+    fn has_commanded_rpm(&self) -> bool {
+        self.inner.has_commanded_rpm()
+    }
+
+    fn get_min_rpm(&self) -> Result<f64> {
+        self.read("GET_MIN_RPM", || self.inner.get_min_rpm())
+    }
+
+    fn get_max_rpm(&self) -> Result<f64> {
+        self.read("GET_MAX_RPM", || self.inner.get_max_rpm())
+    }
+
+    fn get_threshold(&self, threshold: Threshold) -> Result<f64> {
+        // Keyed per threshold, so one the EC doesn't implement doesn't pause the others
+        self.read(&format!("GET_THRESHOLD({threshold:?})"), || {
+            self.inner.get_threshold(threshold)
+        })
+    }
+
+    fn get_sample_period(&self) -> Result<u32> {
+        self.read("GET_SAMPLE_PERIOD", || self.inner.get_sample_period())
+    }
+
+    fn set_sample_period(&self, period_ms: u32) -> Result<()> {
+        self.write(&format!("SET_SAMPLE_PERIOD({period_ms})"), || {
+            self.inner.set_sample_period(period_ms)
+        })
+    }
+
+    fn set_rpm(&self, rpm: f64) -> Result<()> {
+        self.write(&format!("SET_RPM({rpm})"), || self.inner.set_rpm(rpm))
+    }
+
+    fn clear_rpm_override(&self) -> Result<()> {
+        self.write("CLEAR_RPM_OVERRIDE", || self.inner.clear_rpm_override())
+    }
+
+    fn get_bst(&self) -> Result<battery::BstData> {
+        self.read("GET_BST", || self.inner.get_bst())
+    }
+
+    fn get_bix(&self) -> Result<battery::BixData> {
+        self.read("GET_BIX", || self.inner.get_bix())
+    }
+
+    fn get_bif(&self) -> Result<battery::BifData> {
+        self.read("GET_BIF", || self.inner.get_bif())
+    }
+
+    fn get_battery_extended(&self) -> Result<battery::BatteryExtended> {
+        self.read("GET_BATTERY_EXTENDED", || self.inner.get_battery_extended())
+    }
+
+    fn get_power_source(&self) -> Result<battery::PowerSource> {
+        self.read("GET_PSR", || self.inner.get_power_source())
+    }
+
+    fn set_btp(&self, trippoint: u32) -> Result<()> {
+        self.write(&format!("SET_BTP({trippoint})"), || self.inner.set_btp(trippoint))
+    }
+
+    fn get_btp(&self) -> Result<u32> {
+        self.read("GET_BTP", || self.inner.get_btp())
+    }
+
+    fn get_charge_current_limit(&self) -> Result<u32> {
+        self.read("GET_CHARGE_CURRENT_LIMIT", || self.inner.get_charge_current_limit())
+    }
+
+    fn set_charge_current_limit(&self, limit_ma: u32) -> Result<()> {
+        self.write(&format!("SET_CHARGE_CURRENT_LIMIT({limit_ma})"), || {
+            self.inner.set_charge_current_limit(limit_ma)
+        })
+    }
+
+    fn get_var(&self, guid: uuid::Uuid) -> Result<u32> {
+        self.read_once(&format!("GET_VAR({guid})"), || self.inner.get_var(guid))
+    }
+
+    fn set_var(&self, guid: uuid::Uuid, value: u32) -> Result<()> {
+        self.write(&format!("SET_VAR({guid}, {value})"), || self.inner.set_var(guid, value))
+    }
+
+    fn get_firmware_version(&self) -> Result<String> {
+        self.read("GET_FW_VERSION", || self.inner.get_firmware_version())
+    }
+
+    fn subscribe_ec_events(&self) -> Option<std::sync::mpsc::Receiver<crate::events::EcEvent>> {
+        self.inner.subscribe_ec_events()
+    }
+
+    fn is_unsupported(&self, error: &Report) -> bool {
+        self.inner.is_unsupported(error)
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+}
+
+impl<S: Source> RtcSource for SourceProxy<S> {
+    fn get_capabilities(&self) -> Result<TimeAlarmDeviceCapabilities> {
+        self.read("GET_CAPABILITIES", || self.inner.get_capabilities())
+    }
+
+    fn get_real_time(&self) -> Result<AcpiTimestamp> {
+        self.read("GET_REAL_TIME", || self.inner.get_real_time())
+    }
+
+    fn get_wake_status(&self, timer_id: AcpiTimerId) -> Result<TimerStatus> {
+        self.read(&format!("GET_WAKE_STATUS({})", u32::from(timer_id)), || {
+            self.inner.get_wake_status(timer_id)
+        })
+    }
+
+    fn get_expired_timer_wake_policy(&self, timer_id: AcpiTimerId) -> Result<AlarmExpiredWakePolicy> {
+        self.read(&format!("GET_TIMER_WAKE_POLICY({})", u32::from(timer_id)), || {
+            self.inner.get_expired_timer_wake_policy(timer_id)
+        })
+    }
+
+    fn get_timer_value(&self, timer_id: AcpiTimerId) -> Result<AlarmTimerSeconds> {
+        self.read(&format!("GET_TIMER_VALUE({})", u32::from(timer_id)), || {
+            self.inner.get_timer_value(timer_id)
+        })
+    }
+
+    fn set_timer_value(&self, timer_id: AcpiTimerId, seconds: AlarmTimerSeconds) -> Result<()> {
+        self.write(
+            &format!("SET_TIMER_VALUE({}, {})", u32::from(timer_id), seconds.0),
+            || self.inner.set_timer_value(timer_id, seconds),
+        )
+    }
+
+    fn set_expired_timer_wake_policy(&self, timer_id: AcpiTimerId, policy: AlarmExpiredWakePolicy) -> Result<()> {
+        self.write(
+            &format!("SET_TIMER_WAKE_POLICY({}, {})", u32::from(timer_id), policy.0),
+            || self.inner.set_expired_timer_wake_policy(timer_id, policy),
+        )
+    }
+}
+
+impl<S: Source> UcsiSource for SourceProxy<S> {
+    fn get_connector_count(&self) -> Result<u8> {
+        self.read("GET_CONNECTOR_COUNT", || self.inner.get_connector_count())
+    }
+
+    fn get_alternate_modes(&self, connector: u8) -> Result<Vec<ucsi::AltMode>> {
+        self.read(&format!("GET_ALTERNATE_MODES({connector})"), || {
+            self.inner.get_alternate_modes(connector)
+        })
+    }
+
+    fn get_connector_status(&self, connector: u8) -> Result<ucsi::ConnectorStatus> {
+        self.read(&format!("GET_CONNECTOR_STATUS({connector})"), || {
+            self.inner.get_connector_status(connector)
+        })
+    }
+
+    fn get_current_rdo(&self, connector: u8) -> Result<ucsi::Rdo> {
+        self.read(&format!("GET_CURRENT_RDO({connector})"), || {
+            self.inner.get_current_rdo(connector)
+        })
+    }
+
+    fn get_cable_property(&self, connector: u8) -> Result<ucsi::CableProperty> {
+        self.read(&format!("GET_CABLE_PROPERTY({connector})"), || {
+            self.inner.get_cable_property(connector)
+        })
+    }
+
+    fn connector_reset(&self, connector: u8, hard: bool) -> Result<()> {
+        self.write(&format!("CONNECTOR_RESET({connector}, {hard})"), || {
+            self.inner.connector_reset(connector, hard)
+        })
+    }
+
+    fn swap_data_role(&self, connector: u8) -> Result<()> {
+        self.write(&format!("SET_UOR({connector})"), || {
+            self.inner.swap_data_role(connector)
+        })
+    }
+
+    fn get_error_status(&self, connector: u8) -> Result<ucsi::ErrorStatus> {
+        self.read_once(&format!("GET_ERROR_STATUS({connector})"), || {
+            self.inner.get_error_status(connector)
+        })
+    }
+
+    fn subscribe_events(&self) -> Option<std::sync::mpsc::Receiver<ucsi::UcsiEvent>> {
+        self.inner.subscribe_events()
+    }
+}
@@ -0,0 +1,168 @@
+use crate::Source;
+use color_eyre::Result;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// Rows are buffered, so bound how much a crash can lose
+const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Csv,
+    Ndjson,
+}
+
+enum Value {
+    Number(String),
+    Text(String),
+}
+
+/// One timestamped reading of every value
+///
+/// Shared by the recorder and the web endpoint, so both report the same fields under the same names.
+pub struct Snapshot {
+    // Column name and reading, in column order
+    fields: Vec<(&'static str, Option<Value>)>,
+}
+
+impl Snapshot {
+    /// Read every value from `source`, a failed read leaves its field empty
+    pub fn read<S: Source>(source: &S) -> Self {
+        Self {
+            fields: read_all(source),
+        }
+    }
+
+    /// The snapshot as a single line JSON object, failed reads as `null`
+    pub fn to_json(&self) -> String {
+        let members: Vec<String> = self
+            .fields
+            .iter()
+            .map(|(name, value)| match value {
+                Some(Value::Number(value)) => format!("\"{name}\":{value}"),
+                Some(Value::Text(value)) => format!("\"{name}\":\"{value}\""),
+                None => format!("\"{name}\":null"),
+            })
+            .collect();
+        format!("{{{}}}", members.join(","))
+    }
+}
+
+/// Appends a timestamped row of every source reading to a file, for soak tests that need the full time series
+///
+/// Readings are taken through the proxy after the tabs update, so values a tab already read this tick are reused
+/// rather than read again, and anything no tab reads is still recorded. Paths ending in `.json`, `.jsonl` or `.ndjson` are written
+/// as one JSON object per line, anything else as CSV.
+pub struct Recorder {
+    writer: BufWriter<File>,
+    format: Format,
+    // A CSV header is only written to an empty file, so appending to an earlier recording stays parseable
+    needs_header: bool,
+    last_flush: Instant,
+}
+
+impl Recorder {
+    /// Open `path` for appending
+    pub fn create(path: &Path) -> Result<Self> {
+        let format = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json" | "jsonl" | "ndjson") => Format::Ndjson,
+            _ => Format::Csv,
+        };
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let needs_header = format == Format::Csv && file.metadata()?.len() == 0;
+
+        log::info!("Recording readings to {}", path.display());
+        Ok(Self {
+            writer: BufWriter::new(file),
+            format,
+            needs_header,
+            last_flush: Instant::now(),
+        })
+    }
+
+    /// Append `snapshot` as one row, a failed read leaves its column empty
+    pub fn sample(&mut self, snapshot: &Snapshot) -> Result<()> {
+        match self.format {
+            Format::Csv => {
+                if self.needs_header {
+                    let header: Vec<&str> = snapshot.fields.iter().map(|(name, _)| *name).collect();
+                    writeln!(self.writer, "{}", header.join(","))?;
+                    self.needs_header = false;
+                }
+                let row: Vec<&str> = snapshot
+                    .fields
+                    .iter()
+                    .map(|(_, value)| match value {
+                        Some(Value::Number(value) | Value::Text(value)) => value.as_str(),
+                        None => "",
+                    })
+                    .collect();
+                writeln!(self.writer, "{}", row.join(","))?;
+            }
+            Format::Ndjson => writeln!(self.writer, "{}", snapshot.to_json())?,
+        }
+
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.writer.flush()?;
+            self.last_flush = Instant::now();
+        }
+        Ok(())
+    }
+}
+
+// Column name and reading, in column order
+fn read_all<S: Source>(source: &S) -> Vec<(&'static str, Option<Value>)> {
+    let number = |value: Result<String>| value.ok().map(Value::Number);
+    let bst = source.get_bst().ok();
+    let bst_field =
+        |field: fn(&crate::battery::BstData) -> u32| bst.as_ref().map(|bst| Value::Number(field(bst).to_string()));
+
+    vec![
+        (
+            "time",
+            Some(Value::Text(
+                chrono::Local::now()
+                    .format(crate::common::EXPORT_TIME_FORMAT)
+                    .to_string(),
+            )),
+        ),
+        (
+            "temperature",
+            number(source.get_temperature().map(|temp| format!("{temp:.1}"))),
+        ),
+        ("rpm", number(source.get_rpm().map(|rpm| rpm.round().to_string()))),
+        (
+            "commanded_rpm",
+            number(source.get_commanded_rpm().map(|rpm| rpm.round().to_string())),
+        ),
+        ("bst_state", bst_field(|bst| bst.state.bits())),
+        ("bst_rate", bst_field(|bst| bst.rate)),
+        ("bst_capacity", bst_field(|bst| bst.capacity)),
+        ("bst_voltage", bst_field(|bst| bst.voltage)),
+        ("btp", number(source.get_btp().map(|btp| btp.to_string()))),
+        (
+            "power_source",
+            source
+                .get_power_source()
+                .ok()
+                .map(|power| Value::Text(power.as_str().to_string())),
+        ),
+        (
+            "rtc_time",
+            source.get_real_time().ok().map(|timestamp| {
+                let time = timestamp.datetime;
+                Value::Text(format!(
+                    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+                    time.year(),
+                    u8::from(time.month()),
+                    time.day(),
+                    time.hour(),
+                    time.minute(),
+                    time.second()
+                ))
+            }),
+        ),
+    ]
+}
@@ -1,6 +1,9 @@
 use crate::common;
+use crate::widgets::confirm::Confirm;
+use crate::widgets::palette::Outcome;
+use crate::widgets::validated_input::ValidatedInput;
 use color_eyre::Result;
-use crossterm::event::Event;
+use crossterm::event::{Event, KeyCode, KeyEventKind};
 use embedded_mcu_hal::time::Datetime;
 use ratatui::{
     prelude::*,
@@ -17,6 +20,55 @@ use crate::{RtcSource, Source};
 
 const LABEL_COLOR: Color = tailwind::SLATE.c200;
 const DATA_NOT_YET_RETRIEVED_MSG: &str = "Data not yet retrieved";
+// Each timer has a value and a wake policy input
+const TIMER_INPUTS: usize = 2;
+
+// Seconds until the timer expires, "off" disarms it
+fn validate_timer_value(input: &str) -> Result<AlarmTimerSeconds, String> {
+    if input.trim().eq_ignore_ascii_case("off") {
+        return Ok(AlarmTimerSeconds::DISABLED);
+    }
+    common::validate_numeric(input)
+        .map(AlarmTimerSeconds)
+        .map_err(|_| "Expected seconds, or off".to_string())
+}
+
+// Seconds to wait after expiring before waking, "never" to not wake at all
+fn validate_wake_policy(input: &str) -> Result<AlarmExpiredWakePolicy, String> {
+    if input.trim().eq_ignore_ascii_case("never") {
+        return Ok(AlarmExpiredWakePolicy::NEVER);
+    }
+    common::validate_numeric(input)
+        .map(AlarmExpiredWakePolicy)
+        .map_err(|_| "Expected seconds, or never".to_string())
+}
+
+// Writes to a timer, which can arm a wake so ask first by default
+#[derive(Clone, Copy)]
+enum Command {
+    SetTimer(AlarmTimerSeconds),
+    SetWakePolicy(AlarmExpiredWakePolicy),
+}
+
+impl Command {
+    // Name used by --confirm
+    fn name(&self) -> &'static str {
+        match self {
+            Self::SetTimer(_) => "set-timer",
+            Self::SetWakePolicy(_) => "set-wake-policy",
+        }
+    }
+
+    // What the command does to the timer named `timer`
+    fn description(&self, timer: &str) -> String {
+        match *self {
+            Self::SetTimer(AlarmTimerSeconds::DISABLED) => format!("Disarm the {timer} timer"),
+            Self::SetTimer(seconds) => format!("Arm the {timer} timer for {} seconds", seconds.0),
+            Self::SetWakePolicy(AlarmExpiredWakePolicy::NEVER) => format!("Never wake on the {timer} timer"),
+            Self::SetWakePolicy(policy) => format!("Wake {} seconds after the {timer} timer expires", policy.0),
+        }
+    }
+}
 
 mod rtc_timer {
     use super::*;
@@ -26,6 +78,10 @@ mod rtc_timer {
         value: Result<AlarmTimerSeconds>,
         wake_policy: Result<AlarmExpiredWakePolicy>,
         timer_status: Result<TimerStatus>,
+        // Per message line, highlighted briefly when it changes
+        changed: [common::Changed<String>; 3],
+        value_input: ValidatedInput<AlarmTimerSeconds>,
+        wake_policy_input: ValidatedInput<AlarmExpiredWakePolicy>,
     }
 
     impl RtcTimer {
@@ -33,6 +89,12 @@ mod rtc_timer {
             self.value = source.get_timer_value(self.timer_id);
             self.wake_policy = source.get_expired_timer_wake_policy(self.timer_id);
             self.timer_status = source.get_wake_status(self.timer_id);
+
+            // Time remaining counts down every second while armed, so only the other lines are highlighted
+            let messages = self.messages();
+            for (changed, message) in self.changed.iter_mut().zip(messages).skip(1) {
+                changed.update(message);
+            }
         }
 
         pub fn new(timer_id: AcpiTimerId) -> Self {
@@ -41,19 +103,99 @@ mod rtc_timer {
                 value: Err(color_eyre::eyre::eyre!(DATA_NOT_YET_RETRIEVED_MSG)),
                 wake_policy: Err(color_eyre::eyre::eyre!(DATA_NOT_YET_RETRIEVED_MSG)),
                 timer_status: Err(color_eyre::eyre::eyre!(DATA_NOT_YET_RETRIEVED_MSG)),
+                changed: Default::default(),
+                value_input: ValidatedInput::new(validate_timer_value),
+                wake_policy_input: ValidatedInput::new(validate_wake_policy),
+            }
+        }
+
+        // Feed an event to input `input`, 0 for the value and 1 for the wake policy, returning the write on Enter
+        pub fn handle_event(&mut self, input: usize, evt: &Event) -> Option<Command> {
+            let Event::Key(key) = evt else {
+                return None;
+            };
+            if key.code != KeyCode::Enter || key.kind != KeyEventKind::Press {
+                match input {
+                    0 => self.value_input.handle_event(evt),
+                    _ => self.wake_policy_input.handle_event(evt),
+                }
+                return None;
+            }
+
+            match input {
+                0 => self.value_input.submit().map(Command::SetTimer),
+                _ => self.wake_policy_input.submit().map(Command::SetWakePolicy),
+            }
+        }
+
+        // Send `command`, showing a failure on the input it came from
+        pub fn run(&mut self, command: Command, source: &impl RtcSource) {
+            let result = match command {
+                Command::SetTimer(seconds) => source.set_timer_value(self.timer_id, seconds),
+                Command::SetWakePolicy(policy) => source.set_expired_timer_wake_policy(self.timer_id, policy),
+            };
+            match (result, command) {
+                (Ok(()), _) => self.update(source),
+                (Err(e), Command::SetTimer(_)) => self.value_input.set_error(format!("{e}")),
+                (Err(e), Command::SetWakePolicy(_)) => self.wake_policy_input.set_error(format!("{e}")),
             }
         }
 
-        pub fn render(&self, title: &str, area: Rect, buf: &mut Buffer) {
-            let is_healthy = self.value.is_ok() && self.wake_policy.is_ok() && self.timer_status.is_ok();
-            let title = common::title_str_with_status(title, is_healthy);
+        pub fn is_healthy(&self) -> bool {
+            self.value.is_ok() && self.wake_policy.is_ok() && self.timer_status.is_ok()
+        }
+
+        // `focus` is the focused input, as for handle_event
+        pub fn render(&self, title: &str, read_only: bool, focus: Option<usize>, area: Rect, buf: &mut Buffer) {
+            let title = common::title_str_with_status(title, self.is_healthy());
+            let block = common::title_block(&title, 0, LABEL_COLOR);
+            let inner = block.inner(area);
+            block.render(area, buf);
+
+            let [messages_area, inputs_area] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(inner);
+            let [value_area, wake_policy_area] = common::area_split(inputs_area, Direction::Horizontal, 50, 50);
+
+            let lines: Vec<Line<'_>> = self
+                .messages()
+                .into_iter()
+                .zip(&self.changed)
+                .map(|(message, changed)| Line::raw(message).style(changed.style()))
+                .collect();
+            Paragraph::new(lines).render(messages_area, buf);
+
+            self.value_input.render(
+                "Arm seconds | off <ENTER>",
+                read_only,
+                focus == Some(0),
+                value_area,
+                buf,
+            );
+            self.wake_policy_input.render(
+                "Wake policy seconds | never <ENTER>",
+                read_only,
+                focus == Some(1),
+                wake_policy_area,
+                buf,
+            );
+        }
+
+        // Values as decoded from the EC, labelled with `name`
+        pub fn raw_values(&self, name: &str) -> Vec<(String, String)> {
+            vec![
+                (format!("{name} _TIV"), common::raw_value(&self.value)),
+                (format!("{name} _TIP"), common::raw_value(&self.wake_policy)),
+                (format!("{name} _GWS"), common::raw_value(&self.timer_status)),
+            ]
+        }
 
-            Paragraph::new(vec![
-                Line::raw(format_result("Time remaining: ", &self.value, |value| match *value {
+        pub fn messages(&self) -> Vec<String> {
+            vec![
+                format_result("Time remaining: ", &self.value, |value| match *value {
                     AlarmTimerSeconds::DISABLED => "Timer not set".to_string(),
                     seconds => format!("{} seconds", seconds.0),
-                })),
-                Line::raw(format_result(
+                }),
+                format_result(
                     "Wake policy:    ",
                     &self.wake_policy,
                     |wake_policy| match *wake_policy {
@@ -61,8 +203,8 @@ mod rtc_timer {
                         AlarmExpiredWakePolicy::INSTANTLY => "instantly".to_string(),
                         wake_policy => format!("after {} seconds", wake_policy.0),
                     },
-                )),
-                Line::raw(format_result("Timer status:   ", &self.timer_status, |timer_status| {
+                ),
+                format_result("Timer status:   ", &self.timer_status, |timer_status| {
                     format!(
                         "{}, {}",
                         if timer_status.timer_expired() {
@@ -76,10 +218,8 @@ mod rtc_timer {
                             "did not trigger wake".to_string()
                         }
                     )
-                })),
-            ])
-            .block(common::title_block(&title, 0, LABEL_COLOR))
-            .render(area, buf);
+                }),
+            ]
         }
     }
 
@@ -99,6 +239,13 @@ pub struct Rtc<S: Source> {
 
     capabilities: Result<TimeAlarmDeviceCapabilities>,
     timestamp: Result<AcpiTimestamp>,
+    // Time zone and DST, highlighted briefly when either changes
+    zone_changed: common::Changed<(String, &'static str)>,
+    // Inputs are numbered timer by timer, TIMER_INPUTS to each
+    focus: common::Focus,
+    // Names of the commands that ask first, and the one waiting on an answer with the timer it's for
+    confirm: Vec<String>,
+    pending: Option<Confirm<(usize, Command)>>,
 }
 
 impl<S: Source> Module for Rtc<S> {
@@ -108,16 +255,59 @@ impl<S: Source> Module for Rtc<S> {
 
     fn update(&mut self) {
         // Capabilities should be static, so don't try to update after a successful fetch
-        if self.capabilities.is_err() {
-            self.capabilities = self.source.get_capabilities();
-        }
+        common::retry_static(&mut self.capabilities, || self.source.get_capabilities());
         self.timestamp = self.source.get_real_time();
+        if let Ok(timestamp) = &self.timestamp {
+            self.zone_changed
+                .update((format_time_zone(timestamp.time_zone), format_dst(timestamp.dst_status)));
+        }
         for timer in &mut self.timers {
             timer.update(&self.source);
         }
     }
 
-    fn handle_event(&mut self, _evt: &Event) {}
+    fn handle_event(&mut self, evt: &Event) {
+        if let Some(pending) = &self.pending {
+            match pending.handle_event(evt) {
+                Outcome::Pending => {}
+                Outcome::Cancel => self.pending = None,
+                Outcome::Run((timer, command)) => {
+                    self.pending = None;
+                    self.timers[timer].run(command, &self.source);
+                }
+            }
+            return;
+        }
+
+        // The timer inputs can't be edited in read-only mode
+        let inputs = if self.source.is_read_only() {
+            0
+        } else {
+            self.timers.len() * TIMER_INPUTS
+        };
+        if let Event::Key(key) = evt
+            && key.kind == KeyEventKind::Press
+            && self.focus.handle_key(key.code, inputs)
+        {
+            // Focus moved
+        } else if let Some(index) = (0..inputs).find(|&index| self.focus.is(index))
+            && let Some(command) = self.timers[index / TIMER_INPUTS].handle_event(index % TIMER_INPUTS, evt)
+        {
+            self.request_command(index / TIMER_INPUTS, command);
+        }
+    }
+
+    fn has_focus(&self) -> bool {
+        // The prompt needs y/n, which would otherwise be app shortcuts
+        self.focus.any() || self.pending.is_some()
+    }
+
+    fn copy_value(&self) -> Option<String> {
+        self.timestamp
+            .as_ref()
+            .ok()
+            .map(|timestamp| format_time(timestamp.datetime))
+    }
 
     fn render(&self, area: Rect, buf: &mut Buffer) {
         let is_healthy = self.capabilities.is_ok() && self.timestamp.is_ok();
@@ -127,33 +317,46 @@ impl<S: Source> Module for Rtc<S> {
         let [general_area, timers_area] = common::area_split(area, Direction::Vertical, 70, 30);
         let [ac_area, dc_area] = common::area_split(timers_area, Direction::Horizontal, 50, 50);
 
-        let time_messages = match &self.timestamp {
-            Ok(timestamp) => vec![
-                format!("Time:      {}", format_time(timestamp.datetime)),
-                format!("Time Zone: {}", format_time_zone(timestamp.time_zone)),
-                format!("DST:       {}", format_dst(timestamp.dst_status)),
-                "".to_string(),
-            ],
-            Err(err) => vec![format!("Error retrieving RTC time: {}", err)],
-        };
-
-        let capabilities_messages: Vec<String> = match &self.capabilities {
-            Ok(capabilities) => format_capabilities(capabilities),
-            Err(err) => vec![format!("Error retrieving RTC capabilities: {}", err)],
-        };
-
-        let all_messages: Vec<Line<'_>> = time_messages
+        // Time zone and DST are the second and third lines when the time was read
+        let all_messages: Vec<Line<'_>> = self
+            .messages()
             .into_iter()
-            .chain(capabilities_messages)
-            .map(Line::raw)
+            .enumerate()
+            .map(|(index, message)| match index {
+                1 | 2 if self.timestamp.is_ok() => Line::raw(message).style(self.zone_changed.style()),
+                _ => Line::raw(message),
+            })
             .collect();
-
         Paragraph::new(all_messages).block(title).render(general_area, buf);
 
-        self.get_timer(AcpiTimerId::AcPower)
-            .render("AC Power Timer", ac_area, buf);
-        self.get_timer(AcpiTimerId::DcPower)
-            .render("DC Power Timer", dc_area, buf);
+        self.render_timer(AcpiTimerId::AcPower, "AC Power Timer", ac_area, buf);
+        self.render_timer(AcpiTimerId::DcPower, "DC Power Timer", dc_area, buf);
+
+        if let Some(pending) = &self.pending {
+            pending.render(area, buf);
+        }
+    }
+
+    fn raw_values(&self) -> Vec<(String, String)> {
+        let mut values = vec![
+            ("_GCP".to_string(), common::raw_value(&self.capabilities)),
+            ("_GRT".to_string(), common::raw_value(&self.timestamp)),
+        ];
+        values.extend(self.get_timer(AcpiTimerId::AcPower).raw_values("AC timer"));
+        values.extend(self.get_timer(AcpiTimerId::DcPower).raw_values("DC timer"));
+        values
+    }
+
+    fn report(&self) -> String {
+        // Errors are already spelled out in the messages, so sections are never elided
+        let ac_timer = self.get_timer(AcpiTimerId::AcPower);
+        let dc_timer = self.get_timer(AcpiTimerId::DcPower);
+        [
+            common::report_section("Real-time Clock", true, self.messages()),
+            common::report_section("AC Power Timer", true, ac_timer.messages()),
+            common::report_section("DC Power Timer", true, dc_timer.messages()),
+        ]
+        .concat()
     }
 }
 
@@ -214,16 +417,18 @@ fn format_capabilities(capabilities: &TimeAlarmDeviceCapabilities) -> Vec<String
     ]
 }
 
-fn format_time(time: Datetime) -> String {
-    format!(
-        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
-        time.year(),
-        u8::from(time.month()),
-        time.day(),
-        time.hour(),
-        time.minute(),
-        time.second()
-    )
+pub(crate) fn format_time(time: Datetime) -> String {
+    let date = format!("{:04}-{:02}-{:02}", time.year(), u8::from(time.month()), time.day());
+    if common::twelve_hour() {
+        let hour = match time.hour() % 12 {
+            0 => 12,
+            hour => hour,
+        };
+        let suffix = if time.hour() < 12 { "AM" } else { "PM" };
+        format!("{date} {hour:02}:{:02}:{:02} {suffix}", time.minute(), time.second())
+    } else {
+        format!("{date} {:02}:{:02}:{:02}", time.hour(), time.minute(), time.second())
+    }
 }
 
 fn format_time_zone(tz: AcpiTimeZone) -> String {
@@ -238,19 +443,64 @@ fn format_time_zone(tz: AcpiTimeZone) -> String {
 }
 
 impl<S: Source> Rtc<S> {
-    pub fn new(source: S) -> Self {
+    pub fn new(source: S, confirm: Vec<String>) -> Self {
         let mut result = Self {
             source,
             capabilities: Err(color_eyre::eyre::eyre!(DATA_NOT_YET_RETRIEVED_MSG)),
             timestamp: Err(color_eyre::eyre::eyre!(DATA_NOT_YET_RETRIEVED_MSG)),
+            zone_changed: Default::default(),
             timers: [RtcTimer::new(AcpiTimerId::AcPower), RtcTimer::new(AcpiTimerId::DcPower)],
+            focus: common::Focus::default(),
+            confirm,
+            pending: None,
         };
 
         result.update();
         result
     }
 
+    // Send a write to timer `timer`, or ask first if it was configured to need confirmation
+    fn request_command(&mut self, timer: usize, command: Command) {
+        if self.confirm.iter().any(|name| name == command.name()) {
+            let name = if timer == AcpiTimerId::AcPower as usize {
+                "AC"
+            } else {
+                "DC"
+            };
+            let message = format!("{}?", command.description(name));
+            self.pending = Some(Confirm::new(message, (timer, command)));
+        } else {
+            self.timers[timer].run(command, &self.source);
+        }
+    }
+
     fn get_timer(&self, timer_id: AcpiTimerId) -> &RtcTimer {
         &self.timers[timer_id as usize]
     }
+
+    fn render_timer(&self, timer_id: AcpiTimerId, title: &str, area: Rect, buf: &mut Buffer) {
+        let first_input = timer_id as usize * TIMER_INPUTS;
+        let focus = (0..TIMER_INPUTS).find(|&input| self.focus.is(first_input + input));
+        self.get_timer(timer_id)
+            .render(title, self.source.is_read_only(), focus, area, buf);
+    }
+
+    fn messages(&self) -> Vec<String> {
+        let time_messages = match &self.timestamp {
+            Ok(timestamp) => vec![
+                format!("Time:      {}", format_time(timestamp.datetime)),
+                format!("Time Zone: {}", format_time_zone(timestamp.time_zone)),
+                format!("DST:       {}", format_dst(timestamp.dst_status)),
+                "".to_string(),
+            ],
+            Err(err) => vec![format!("Error retrieving RTC time: {}", err)],
+        };
+
+        let capabilities_messages: Vec<String> = match &self.capabilities {
+            Ok(capabilities) => format_capabilities(capabilities),
+            Err(err) => vec![format!("Error retrieving RTC capabilities: {}", err)],
+        };
+
+        time_messages.into_iter().chain(capabilities_messages).collect()
+    }
 }
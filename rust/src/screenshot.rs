@@ -0,0 +1,126 @@
+use ratatui::{
+    buffer::Buffer,
+    style::{Color, Modifier, Style},
+    text::Span,
+};
+
+/// Render a buffer as text, one line per row, either plain or with ANSI escape codes for colors and modifiers
+pub fn buffer_to_string(buf: &Buffer, ansi: bool) -> String {
+    let area = buf.area;
+    let mut out = String::new();
+
+    for y in area.top()..area.bottom() {
+        let mut line = String::new();
+        let mut style = None;
+        // Cells covered by a wide symbol (emoji, CJK) hold a placeholder that must not be output
+        let mut to_skip = 0;
+
+        for x in area.left()..area.right() {
+            let cell = &buf[(x, y)];
+            if to_skip > 0 {
+                to_skip -= 1;
+                continue;
+            }
+            if cell.skip {
+                continue;
+            }
+
+            if ansi && style != Some(cell.style()) {
+                line.push_str(&sgr(cell.style()));
+                style = Some(cell.style());
+            }
+            line.push_str(cell.symbol());
+            to_skip = Span::raw(cell.symbol()).width().saturating_sub(1);
+        }
+
+        if ansi {
+            line.push_str("\x1b[0m");
+        } else {
+            line.truncate(line.trim_end().len());
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+// Select Graphic Rendition sequence setting exactly this style, starting from a reset
+fn sgr(style: Style) -> String {
+    let mut codes = vec!["0".to_string()];
+
+    const MODIFIERS: [(Modifier, &str); 6] = [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::CROSSED_OUT, "9"),
+    ];
+    for (modifier, code) in MODIFIERS {
+        if style.add_modifier.contains(modifier) {
+            codes.push(code.to_string());
+        }
+    }
+
+    if let Some(fg) = style.fg.and_then(|color| color_code(color, 30)) {
+        codes.push(fg);
+    }
+    if let Some(bg) = style.bg.and_then(|color| color_code(color, 40)) {
+        codes.push(bg);
+    }
+
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+// Color code relative to `base` (30 for foreground, 40 for background), None for the terminal default
+fn color_code(color: Color, base: u8) -> Option<String> {
+    let named = |offset: u8| Some((base + offset).to_string());
+    let bright = |offset: u8| Some((base + 60 + offset).to_string());
+
+    match color {
+        Color::Reset => None,
+        Color::Black => named(0),
+        Color::Red => named(1),
+        Color::Green => named(2),
+        Color::Yellow => named(3),
+        Color::Blue => named(4),
+        Color::Magenta => named(5),
+        Color::Cyan => named(6),
+        Color::Gray => named(7),
+        Color::DarkGray => bright(0),
+        Color::LightRed => bright(1),
+        Color::LightGreen => bright(2),
+        Color::LightYellow => bright(3),
+        Color::LightBlue => bright(4),
+        Color::LightMagenta => bright(5),
+        Color::LightCyan => bright(6),
+        Color::White => bright(7),
+        Color::Indexed(index) => Some(format!("{};5;{index}", base + 8)),
+        Color::Rgb(r, g, b) => Some(format!("{};2;{r};{g};{b}", base + 8)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+
+    #[test]
+    fn wide_symbols_plain() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 8, 1));
+        buf.set_string(0, 0, "🔋a中b", Style::default());
+        assert_eq!(buffer_to_string(&buf, false), "🔋a中b\n");
+    }
+
+    #[test]
+    fn wide_symbols_skip_continuation_cells() {
+        // Whatever the cell under the second half of a wide symbol holds, it isn't output
+        let mut buf = Buffer::empty(Rect::new(0, 0, 4, 1));
+        buf[(0, 0)].set_symbol("🔋");
+        buf[(1, 0)].set_symbol("X");
+        buf[(2, 0)].set_symbol("中");
+        buf[(3, 0)].set_symbol("Y");
+        assert_eq!(buffer_to_string(&buf, false), "🔋中\n");
+        assert_eq!(buffer_to_string(&buf, true), "\x1b[0m🔋中\x1b[0m\n");
+    }
+}
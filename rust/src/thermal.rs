@@ -1,19 +1,41 @@
 use crate::app::Module;
 use crate::common;
+use crate::widgets::validated_input::ValidatedInput;
 use crate::{Source, Threshold};
 use color_eyre::Result;
 use ratatui::{
     buffer::Buffer,
     crossterm::event::{Event, KeyCode, KeyEventKind},
-    layout::{Direction, Rect},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style, Stylize, palette::tailwind},
     text::{Line, Span},
-    widgets::{Block, Gauge, Paragraph, Widget},
+    widgets::{Gauge, Paragraph, Widget},
 };
-use tui_input::{Input, backend::crossterm::EventHandler};
 
 const LABEL_COLOR: Color = tailwind::SLATE.c200;
-const MAX_SAMPLES: usize = 60;
+const CLAMP_COLOR: Color = tailwind::YELLOW.c500;
+const ALERT_COLOR: Color = tailwind::RED.c700;
+const COMMANDED_COLOR: Color = tailwind::SLATE.c400;
+const TEMP_COLOR_NORMAL: Color = tailwind::GREEN.c500;
+const TEMP_COLOR_WARNING: Color = tailwind::YELLOW.c500;
+const TEMP_COLOR_CRITICAL: Color = tailwind::RED.c500;
+const TREND_COLOR_FALLING: Color = tailwind::SKY.c400;
+const MAX_ALERTS: usize = 20;
+// Samples kept for scrolling back through the graphs
+const HISTORY_SAMPLES: usize = 3600;
+// Temperature trend is fitted over this much history, needing a few samples so one noisy reading doesn't dominate
+const TREND_SPAN: std::time::Duration = std::time::Duration::from_secs(60);
+const TREND_MIN_SAMPLES: usize = 5;
+// °C per minute beyond which the trend is colored as rising or falling, and rising fast
+const TREND_STEADY: f64 = 0.2;
+const TREND_FAST: f64 = 1.0;
+// Sensor sample periods in ms accepted for setting, faster would swamp the EC and slower makes the fan loop sluggish
+const SAMPLE_PERIOD_RANGE: std::ops::RangeInclusive<u32> = 10..=10_000;
+// Without a commanded RPM to read back, a set RPM is checked against the tach once the fan has had time to spin up
+// or down, and only counts as a mismatch beyond a few percent of the target or a minimum for slow speeds
+const ECHO_SETTLE: std::time::Duration = std::time::Duration::from_secs(10);
+const ECHO_TOLERANCE: f64 = 0.05;
+const ECHO_MIN_TOLERANCE_RPM: f64 = 100.0;
 
 fn get_sensor_tmp<S: Source>(source: &S) -> Result<f64> {
     source.get_temperature()
@@ -29,6 +51,33 @@ fn get_sensor_thresholds<S: Source>(_source: &S) -> Result<SensorThresholds> {
     })
 }
 
+// Decimal as the RPM input always took, including fractions, or hex and binary as the other inputs take
+fn validate_rpm(input: &str) -> Result<f64, String> {
+    let trimmed = input.trim();
+    let prefixed = ["0x", "0X", "0b", "0B"]
+        .iter()
+        .any(|prefix| trimmed.starts_with(prefix));
+    let rpm = if prefixed {
+        common::parse_numeric(trimmed).map(f64::from)
+    } else {
+        trimmed.parse::<f64>().ok().filter(|rpm| rpm.is_finite())
+    };
+    rpm.ok_or_else(|| "Expected an RPM: decimal, 0x hex or 0b binary".to_string())
+}
+
+fn validate_sample_period(input: &str) -> Result<u32, String> {
+    let period = common::validate_numeric(input)?;
+    if SAMPLE_PERIOD_RANGE.contains(&period) {
+        Ok(period)
+    } else {
+        Err(format!(
+            "Expected {} to {} ms",
+            SAMPLE_PERIOD_RANGE.start(),
+            SAMPLE_PERIOD_RANGE.end()
+        ))
+    }
+}
+
 fn get_fan_rpm<S: Source>(source: &S) -> Result<f64> {
     source.get_rpm()
 }
@@ -37,6 +86,41 @@ fn set_fan_rpm<S: Source>(source: &S, rpm: f64) -> Result<()> {
     source.set_rpm(rpm)
 }
 
+// Clamp a requested RPM to the fan's bounds, returning the value to send and whether it was adjusted
+fn clamp_rpm(rpm: f64, bounds: &FanRpmBounds) -> (f64, bool) {
+    if bounds.min > bounds.max {
+        return (rpm, false);
+    }
+    let clamped = rpm.clamp(bounds.min, bounds.max);
+    (clamped, clamped != rpm)
+}
+
+// Check a set RPM against a settled tach reading, which never matches it exactly
+fn settled_echo(set: f64, read: Result<f64>) -> common::Echo<u32> {
+    let tolerance = (set * ECHO_TOLERANCE).max(ECHO_MIN_TOLERANCE_RPM);
+    match read {
+        Ok(read) if (read - set).abs() <= tolerance => common::Echo::Match,
+        Ok(read) => common::Echo::Mismatch {
+            sent: set as u32,
+            read: read as u32,
+        },
+        Err(_) => common::Echo::ReadFailed { sent: set as u32 },
+    }
+}
+
+// RPM as a percentage of the min..max envelope, pinned to 0 if the envelope is empty
+fn rpm_percent(rpm: f64, bounds: &FanRpmBounds) -> u16 {
+    let span = bounds.max - bounds.min;
+    if span <= 0.0 {
+        return 0;
+    }
+    (((rpm - bounds.min) / span) * 100.0).clamp(0.0, 100.0) as u16
+}
+
+fn clear_fan_rpm_override<S: Source>(source: &S) -> Result<()> {
+    source.clear_rpm_override()
+}
+
 fn get_fan_bounds<S: Source>(source: &S) -> Result<FanRpmBounds> {
     let min = source.get_min_rpm()?;
     let max = source.get_max_rpm()?;
@@ -44,15 +128,7 @@ fn get_fan_bounds<S: Source>(source: &S) -> Result<FanRpmBounds> {
     Ok(FanRpmBounds { min, max })
 }
 
-fn get_fan_levels<S: Source>(source: &S) -> Result<FanStateLevels> {
-    let on = source.get_threshold(Threshold::On)?;
-    let ramping = source.get_threshold(Threshold::Ramping)?;
-    let max = source.get_threshold(Threshold::Max)?;
-
-    Ok(FanStateLevels { on, ramping, max })
-}
-
-#[derive(Default)]
+#[derive(Debug, Default)]
 struct SensorThresholds {
     _warn_low: f64,
     warn_high: f64,
@@ -64,17 +140,63 @@ struct SensorThresholds {
 struct SensorState {
     skin_temp: f64,
     temp_success: bool,
+    temp_freshness: common::Freshness,
     thresholds: SensorThresholds,
     thresholds_success: bool,
-    samples: common::SampleBuf<f64, MAX_SAMPLES>,
+    samples: common::SampleBuf<f64, HISTORY_SAMPLES>,
+    // Whether the sensor was above its Max threshold at the last reading, so alerts fire on the crossing only
+    above_max: bool,
+    // Timestamped trip and fan stall alerts, most recent last
+    alerts: Vec<String>,
+    // Lowest and highest temperature seen since the last reset, None until the first reading
+    observed: Option<(f64, f64)>,
+    // In ms, None if the EC doesn't report it
+    sample_period: Option<u32>,
+    // Read-back of the last sample period we set
+    sample_period_echo: Option<common::Echo<u32>>,
 }
 
 impl SensorState {
+    // Check the latest reading against the Max threshold, returning an alert on a rising crossing
+    fn check_trip(&mut self, name: &str, max: &FanLevel) -> Option<String> {
+        if !self.temp_success || !max.success {
+            return None;
+        }
+
+        let above = self.skin_temp >= max.temp;
+        let crossed = above && !self.above_max;
+        self.above_max = above;
+        if !crossed {
+            return None;
+        }
+
+        let alert = format!(
+            "{} {name} {:.1} °C crossed Max {} °C",
+            common::format_wall_clock(std::time::SystemTime::now()),
+            self.skin_temp,
+            max.temp.round()
+        );
+        self.record_alert(alert.clone());
+        Some(alert)
+    }
+
+    fn record_alert(&mut self, alert: String) {
+        self.alerts.push(alert);
+        if self.alerts.len() > MAX_ALERTS {
+            self.alerts.remove(0);
+        }
+    }
+
     fn update<S: Source>(&mut self, source: &S) {
         if let Ok(temp) = get_sensor_tmp(source) {
             self.skin_temp = temp;
             self.samples.insert(temp);
+            self.observed = Some(match self.observed {
+                Some((min, max)) => (min.min(temp), max.max(temp)),
+                None => (temp, temp),
+            });
             self.temp_success = true;
+            self.temp_freshness.mark();
         } else {
             self.temp_success = false;
         }
@@ -85,64 +207,210 @@ impl SensorState {
         } else {
             self.thresholds_success = false;
         }
+
+        self.sample_period = source.get_sample_period().ok();
     }
 }
 
-#[derive(Default)]
+/// When a fan counts as stalled: commanded above `min_commanded` RPM while the tach reads below `max_rpm`, for
+/// `samples` updates in a row so a fan still spinning up isn't flagged
+#[derive(Debug, Clone, Copy)]
+pub struct StallDetect {
+    pub min_commanded: f64,
+    pub max_rpm: f64,
+    pub samples: usize,
+}
+
+#[derive(Debug, Default)]
 struct FanRpmBounds {
     min: f64,
     max: f64,
 }
 
+// A single fan threshold, tracked separately so one failing read doesn't mask the others
+#[derive(Default)]
+struct FanLevel {
+    temp: f64,
+    success: bool,
+    changed: common::Changed<f64>,
+}
+
+impl FanLevel {
+    fn update<S: Source>(&mut self, source: &S, threshold: Threshold) {
+        if let Ok(temp) = source.get_threshold(threshold) {
+            self.temp = temp;
+            self.success = true;
+            self.changed.update(temp);
+        } else {
+            self.success = false;
+        }
+    }
+}
+
 #[derive(Default)]
 struct FanStateLevels {
-    on: f64,
-    ramping: f64,
-    max: f64,
+    on: FanLevel,
+    // Only implemented by firmware with fan hysteresis
+    off: FanLevel,
+    ramping: FanLevel,
+    max: FanLevel,
+}
+
+impl FanStateLevels {
+    fn update<S: Source>(&mut self, source: &S) {
+        self.on.update(source, Threshold::On);
+        self.off.update(source, Threshold::Off);
+        self.ramping.update(source, Threshold::Ramping);
+        self.max.update(source, Threshold::Max);
+    }
+
+    // Off is optional, so it doesn't count against the others
+    fn success(&self) -> bool {
+        self.on.success && self.ramping.success && self.max.success
+    }
 }
 
 #[derive(Default)]
 struct FanState {
     rpm: f64,
     rpm_success: bool,
+    rpm_freshness: common::Freshness,
+    // None until we've set or cleared an override, since the mode can't be read back
+    manual: Option<bool>,
+    // Why clearing the override last failed
+    clear_error: Option<String>,
+    // RPM we last set, standing in for the commanded speed when the EC doesn't report it
+    set_rpm: Option<f64>,
+    // Last requested RPM that had to be clamped, and what it was clamped to
+    clamped: Option<(f64, f64)>,
+    // Read-back of the last RPM we set
+    echo: Option<common::Echo<u32>>,
+    // RPM we set and when, until the tach has settled enough to check it, see ECHO_SETTLE
+    echo_pending: Option<(f64, std::time::Instant)>,
     rpm_bounds: FanRpmBounds,
     bounds_success: bool,
     state_levels: FanStateLevels,
-    levels_success: bool,
-    samples: common::SampleBuf<u32, MAX_SAMPLES>,
+    samples: common::SampleBuf<u32, HISTORY_SAMPLES>,
+    // Speed the EC is asking for, if it reports that separately from the tach
+    commanded: Option<f64>,
+    commanded_samples: common::SampleBuf<u32, HISTORY_SAMPLES>,
+    // Whether the fan is spinning, None until known, and how often that has flipped so chatter around On shows
+    running: Option<bool>,
+    switches: usize,
+    // Consecutive updates that looked stalled
+    stall_samples: usize,
+    // Number of fans, None until read, and the tach of each fan after the first, None where the read failed
+    count: Option<u8>,
+    others: Vec<Option<f64>>,
 }
 
 impl FanState {
     fn update<S: Source>(&mut self, source: &S) {
-        if let Ok(rpm) = get_fan_rpm(source) {
+        let rpm = get_fan_rpm(source);
+        if let Ok(rpm) = rpm {
             self.rpm = rpm;
             self.samples.insert(rpm as u32);
             self.rpm_success = true;
+            self.rpm_freshness.mark();
         } else {
             self.rpm_success = false;
         }
 
-        if let Ok(rpm_bounds) = get_fan_bounds(source) {
+        if let Some((set, since)) = self.echo_pending
+            && since.elapsed() >= ECHO_SETTLE
+        {
+            self.echo = Some(settled_echo(set, rpm));
+            self.echo_pending = None;
+        }
+
+        if source.has_commanded_rpm() {
+            self.commanded = source.get_commanded_rpm().ok();
+            if let Some(rpm) = self.commanded {
+                self.commanded_samples.insert(rpm as u32);
+            }
+        }
+
+        // Bounds are static, so only read them until the first success
+        if !self.bounds_success
+            && let Ok(rpm_bounds) = get_fan_bounds(source)
+        {
             self.rpm_bounds = rpm_bounds;
             self.bounds_success = true;
-        } else {
-            self.bounds_success = false;
         }
 
-        if let Ok(state_levels) = get_fan_levels(source) {
-            self.state_levels = state_levels;
-            self.levels_success = true;
-        } else {
-            self.levels_success = false;
+        // The fan count is static too
+        if self.count.is_none() {
+            self.count = source.get_fan_count().ok();
         }
+        self.others = (1..self.count.unwrap_or(1))
+            .map(|fan| source.get_fan_rpm(fan).ok())
+            .collect();
+
+        self.state_levels.update(source);
+        self.update_running();
+    }
+
+    // Tach of every fan in order, None where the read failed
+    fn all_rpms(&self) -> Vec<Option<f64>> {
+        let first = self.rpm_success.then_some(self.rpm);
+        std::iter::once(first).chain(self.others.iter().copied()).collect()
+    }
+
+    // Go by the commanded speed where available, since the tach lags it by a few seconds
+    fn update_running(&mut self) {
+        let running = match self.commanded {
+            Some(commanded) => commanded > 0.0,
+            None if self.rpm_success => self.rpm > 0.0,
+            None => return,
+        };
+        if self.running.is_some_and(|was| was != running) {
+            self.switches += 1;
+        }
+        self.running = Some(running);
+    }
+
+    // Count updates where the fan is driven but not turning, returning an alert once it's been that way long enough
+    fn check_stall(&mut self, detect: &StallDetect) -> Option<String> {
+        let commanded = self.commanded.or(self.set_rpm);
+        let stalled = match commanded {
+            Some(commanded) => self.rpm_success && commanded > detect.min_commanded && self.rpm < detect.max_rpm,
+            None => false,
+        };
+        if !stalled {
+            self.stall_samples = 0;
+            return None;
+        }
+
+        self.stall_samples += 1;
+        (self.stall_samples == detect.samples).then(|| {
+            format!(
+                "{} Fan stalled: commanded {} RPM but tach reads {} for {} samples",
+                common::format_wall_clock(std::time::SystemTime::now()),
+                commanded.unwrap_or_default().round(),
+                self.rpm.round(),
+                detect.samples
+            )
+        })
+    }
+
+    fn is_stalled(&self, detect: &StallDetect) -> bool {
+        self.stall_samples >= detect.samples
     }
 }
 
 pub struct Thermal<S: Source> {
-    rpm_input: Input,
+    rpm_input: ValidatedInput<f64>,
+    sample_period_input: ValidatedInput<u32>,
+    focus: common::Focus,
     sensor: SensorState,
     fan: FanState,
     t: usize,
+    wall_clock: bool,
+    viewport: common::Viewport,
+    bell: bool,
+    // Warning and critical temperatures given on the command line, otherwise the fan levels are used
+    temp_colors: Option<(f64, f64)>,
+    stall_detect: StallDetect,
     source: S,
 }
 
@@ -154,6 +422,19 @@ impl<S: Source> Module for Thermal<S> {
     fn update(&mut self) {
         self.sensor.update(&self.source);
         self.fan.update(&self.source);
+        if let Some(alert) = self.sensor.check_trip("Skin temp", &self.fan.state_levels.max) {
+            log::warn!("Thermal trip: {alert}");
+            if self.bell {
+                common::ring_bell();
+            }
+        }
+        if let Some(alert) = self.fan.check_stall(&self.stall_detect) {
+            log::warn!("{alert}");
+            self.sensor.record_alert(alert);
+            if self.bell {
+                common::ring_bell();
+            }
+        }
         self.t += 1;
     }
 
@@ -164,26 +445,188 @@ impl<S: Source> Module for Thermal<S> {
     }
 
     fn handle_event(&mut self, evt: &Event) {
+        // The RPM and sample period inputs, neither of which can be edited in read-only mode
+        let inputs = if self.source.is_read_only() { 0 } else { 2 };
         if let Event::Key(key) = evt
+            && key.kind == KeyEventKind::Press
+            && self.focus.handle_key(key.code, inputs)
+        {
+            // Focus moved
+        } else if let Event::Key(key) = evt
             && key.code == KeyCode::Enter
             && key.kind == KeyEventKind::Press
+            && self.focus.is(0)
         {
-            if let Ok(rpm) = self.rpm_input.value_and_reset().parse() {
-                let _ = set_fan_rpm(&self.source, rpm);
+            if let Some(requested) = self.rpm_input.submit() {
+                // Only clamp against bounds we actually managed to read
+                let (rpm, adjusted) = if self.fan.bounds_success {
+                    clamp_rpm(requested, &self.fan.rpm_bounds)
+                } else {
+                    (requested, false)
+                };
+                self.fan.clamped = adjusted.then_some((requested, rpm));
+
+                if set_fan_rpm(&self.source, rpm).is_ok() {
+                    self.fan.manual = Some(true);
+                    self.fan.set_rpm = Some(rpm);
+                    // The tach lags the commanded speed, so only a commanded read-back can be checked straight away
+                    if self.source.has_commanded_rpm() {
+                        let read = self.source.get_commanded_rpm().map(|rpm| rpm as u32);
+                        self.fan.echo = Some(common::Echo::check(rpm as u32, read));
+                        self.fan.echo_pending = None;
+                    } else {
+                        self.fan.echo = None;
+                        self.fan.echo_pending = Some((rpm, std::time::Instant::now()));
+                    }
+                }
             }
-        } else {
-            let _ = self.rpm_input.handle_event(evt);
+        } else if let Event::Key(key) = evt
+            && key.code == KeyCode::Enter
+            && key.kind == KeyEventKind::Press
+            && self.focus.is(1)
+        {
+            if let Some(period) = self.sample_period_input.submit()
+                && self.source.set_sample_period(period).is_ok()
+            {
+                let read = self.source.get_sample_period();
+                self.sensor.sample_period = read.as_ref().ok().copied();
+                self.sensor.sample_period_echo = Some(common::Echo::check(period, read));
+            }
+        } else if self.focus.is(0) {
+            self.rpm_input.handle_event(evt);
+        } else if self.focus.is(1) {
+            self.sample_period_input.handle_event(evt);
+        } else if let Event::Key(key) = evt
+            && key.code == KeyCode::Char('o')
+            && key.kind == KeyEventKind::Press
+            && !self.source.is_read_only()
+        {
+            match clear_fan_rpm_override(&self.source) {
+                Ok(()) => {
+                    self.fan.manual = Some(false);
+                    self.fan.clear_error = None;
+                    self.fan.set_rpm = None;
+                    self.fan.echo = None;
+                    self.fan.echo_pending = None;
+                }
+                Err(e) => self.fan.clear_error = Some(e.to_string()),
+            }
+        } else if let Event::Key(key) = evt
+            && key.code == KeyCode::Char('w')
+            && key.kind == KeyEventKind::Press
+        {
+            self.wall_clock = !self.wall_clock;
+        } else if let Event::Key(key) = evt
+            && key.code == KeyCode::Char('r')
+            && key.kind == KeyEventKind::Press
+        {
+            self.sensor.observed = None;
+        } else if let Event::Key(key) = evt
+            && key.kind == KeyEventKind::Press
+            && self.viewport.handle_key(key.code, self.sensor.samples.len())
+        {
+            // Both graphs share a viewport so they stay lined up
+        }
+    }
+
+    fn has_focus(&self) -> bool {
+        self.focus.any()
+    }
+
+    fn commands(&self) -> Vec<(&'static str, KeyCode)> {
+        let mut commands = vec![
+            ("Toggle wall clock axis", KeyCode::Char('w')),
+            ("Reset min / max temperature", KeyCode::Char('r')),
+        ];
+        if !self.source.is_read_only() {
+            commands.push(("Clear fan RPM override", KeyCode::Char('o')));
+        }
+        commands
+    }
+
+    fn raw_values(&self) -> Vec<(String, String)> {
+        let read = |success: bool, value: String| if success { value } else { "Not read".to_string() };
+        let levels = &self.fan.state_levels;
+        let mut values = vec![
+            (
+                "GET_TMP".to_string(),
+                read(self.sensor.temp_success, format!("{:?}", self.sensor.skin_temp)),
+            ),
+            (
+                "Sensor thresholds".to_string(),
+                read(self.sensor.thresholds_success, format!("{:?}", self.sensor.thresholds)),
+            ),
+            (
+                "GET_RPM".to_string(),
+                read(self.fan.rpm_success, format!("{:?}", self.fan.rpm)),
+            ),
+            ("Commanded RPM".to_string(), format!("{:?}", self.fan.commanded)),
+            ("GET_FAN_COUNT".to_string(), format!("{:?}", self.fan.count)),
+            ("Sample period".to_string(), format!("{:?}", self.sensor.sample_period)),
+            (
+                "RPM bounds".to_string(),
+                read(self.fan.bounds_success, format!("{:?}", self.fan.rpm_bounds)),
+            ),
+        ];
+        for (index, rpm) in self.fan.others.iter().enumerate() {
+            values.push((format!("GET_FAN_RPM({})", index + 1), format!("{rpm:?}")));
+        }
+        for (threshold, level) in [
+            (Threshold::On, &levels.on),
+            (Threshold::Off, &levels.off),
+            (Threshold::Ramping, &levels.ramping),
+            (Threshold::Max, &levels.max),
+        ] {
+            values.push((
+                format!("Threshold {threshold:?}"),
+                read(level.success, format!("{:?}", level.temp)),
+            ));
         }
+        values
+    }
+
+    fn copy_value(&self) -> Option<String> {
+        self.sensor
+            .temp_success
+            .then(|| format!("{:.2} °C", self.sensor.skin_temp))
+    }
+
+    fn report(&self) -> String {
+        [
+            common::report_section("Live Temperature", self.sensor.temp_success, self.create_sensor_stats()),
+            common::report_section(
+                "Thresholds",
+                self.sensor.thresholds_success,
+                self.create_sensor_thresholds(),
+            ),
+            common::report_section(
+                "Live Fan RPM",
+                self.fan.rpm_success && self.fan.bounds_success,
+                self.create_fan_stats(),
+            ),
+            common::report_section("Fans", !self.fan.others.is_empty(), self.create_fan_list()),
+            // Levels carry their own per-line status, so always include them
+            common::report_section("Fan State Levels", true, self.create_fan_levels()),
+            common::report_section("Alerts", true, &self.sensor.alerts),
+        ]
+        .concat()
     }
 }
 
 impl<S: Source> Thermal<S> {
-    pub fn new(source: S) -> Self {
+    pub fn new(source: S, bell: bool, temp_colors: Option<(f64, f64)>, stall_detect: StallDetect) -> Self {
         let mut inst = Self {
-            rpm_input: Default::default(),
+            rpm_input: ValidatedInput::new(validate_rpm),
+            sample_period_input: ValidatedInput::new(validate_sample_period),
+            focus: Default::default(),
             sensor: Default::default(),
             fan: Default::default(),
             t: Default::default(),
+            wall_clock: false,
+            viewport: Default::default(),
+            bell,
+            temp_colors,
+            stall_detect,
             source,
         };
 
@@ -191,6 +634,18 @@ impl<S: Source> Thermal<S> {
         inst
     }
 
+    // X-axis title and labels, either relative seconds or wall-clock time
+    fn x_axis<T: Into<f64> + Copy>(
+        &self,
+        samples: &common::SampleBuf<T, HISTORY_SAMPLES>,
+    ) -> (String, [Span<'static>; 3]) {
+        if self.wall_clock {
+            ("Time".to_string(), samples.wall_clock_labels(&self.viewport))
+        } else {
+            ("Time (s)".to_string(), common::time_labels(self.t, &self.viewport))
+        }
+    }
+
     fn render_sensor(&self, area: Rect, buf: &mut Buffer) {
         let [chart_area, widget_area] = common::area_split(area, Direction::Vertical, 70, 30);
         let [stats_area, thresholds_area] = common::area_split(widget_area, Direction::Horizontal, 50, 50);
@@ -211,27 +666,120 @@ impl<S: Source> Thermal<S> {
                 Style::default().bold(),
             ),
         ];
+        let (x_axis, x_labels) = self.x_axis(&self.sensor.samples);
         let graph = common::Graph {
-            title: "Temperature vs Time".to_string(),
-            color: Color::Red,
-            samples: self.sensor.samples.get(),
-            x_axis: "Time (s)".to_string(),
-            x_bounds: [0.0, 60.0],
-            x_labels: common::time_labels(self.t, MAX_SAMPLES),
+            title: "Temperature vs Time".to_string() + &self.viewport.suffix(),
+            color: self.temp_level().map_or(Color::Red, |(color, _)| color),
+            samples: self.sensor.samples.window(&self.viewport),
+            x_axis,
+            x_bounds: self.viewport.x_bounds(),
+            x_labels,
             y_axis: "Temperature (°C)".to_string(),
             y_bounds: [0.0, self.sensor.thresholds.critical + 5.0],
             y_labels,
+            markers: self.fan_level_markers(),
+            series: Vec::new(),
         };
         common::render_chart(area, buf, graph);
     }
 
+    // Green/yellow/red for the current temperature, with a label for above normal so it doesn't rely on color alone
+    // None if there are no thresholds to compare against.
+    fn temp_level(&self) -> Option<(Color, Option<&'static str>)> {
+        let levels = &self.fan.state_levels;
+        let (warning, critical) = match self.temp_colors {
+            Some(thresholds) => thresholds,
+            None if levels.ramping.success && levels.max.success => (levels.ramping.temp, levels.max.temp),
+            None => return None,
+        };
+
+        let temp = self.sensor.skin_temp;
+        Some(if temp >= critical {
+            (TEMP_COLOR_CRITICAL, Some("CRITICAL"))
+        } else if temp >= warning {
+            (TEMP_COLOR_WARNING, Some("WARNING"))
+        } else {
+            (TEMP_COLOR_NORMAL, None)
+        })
+    }
+
+    // Fan state levels as reference lines, skipping any we failed to read
+    fn fan_level_markers(&self) -> Vec<common::GraphMarker> {
+        let levels = &self.fan.state_levels;
+        [
+            ("Fan on", &levels.on, tailwind::GREEN.c500),
+            ("Fan off", &levels.off, tailwind::GREEN.c800),
+            ("Fan ramping", &levels.ramping, tailwind::YELLOW.c500),
+            ("Fan max", &levels.max, tailwind::RED.c500),
+        ]
+        .into_iter()
+        .filter(|(_, level, _)| level.success)
+        .map(|(label, level, color)| common::GraphMarker {
+            label,
+            value: level.temp,
+            color,
+        })
+        .collect()
+    }
+
     fn create_sensor_stats(&self) -> Vec<Line<'static>> {
-        vec![Line::raw(format!("Skin temp: {:.2} °C", self.sensor.skin_temp))]
+        let observed = match self.sensor.observed {
+            Some((min, max)) => format!("{min:.2} / {max:.2} °C"),
+            None => "—".to_string(),
+        };
+        let level = self.temp_level();
+        let label = level
+            .and_then(|(_, label)| label)
+            .map_or_else(String::new, |label| format!(" [{label}]"));
+        let mut lines = vec![
+            Line::styled(
+                format!("Skin temp: {:.2} °C{label}", self.sensor.skin_temp),
+                level.map_or_else(Style::default, |(color, _)| Style::default().fg(color)),
+            ),
+            Line::raw(format!("Min / Max: {observed} (r to reset)")),
+            self.trend_line(),
+        ];
+        if let Some(alert) = self.sensor.alerts.last() {
+            lines.push(Line::styled(
+                format!("Last alert: {alert}"),
+                Style::default().fg(ALERT_COLOR),
+            ));
+        }
+        lines
+    }
+
+    // Temperature slope in °C per minute, red when climbing fast enough to hit throttling soon
+    fn trend_line(&self) -> Line<'static> {
+        let Some(rate) = self
+            .sensor
+            .samples
+            .rate_per_sec(TREND_SPAN, TREND_MIN_SAMPLES)
+            .map(|rate| rate * 60.0)
+        else {
+            return Line::raw("Trend:     —");
+        };
+
+        let color = if rate >= TREND_FAST {
+            TEMP_COLOR_CRITICAL
+        } else if rate >= TREND_STEADY {
+            TEMP_COLOR_WARNING
+        } else if rate <= -TREND_STEADY {
+            TREND_COLOR_FALLING
+        } else {
+            LABEL_COLOR
+        };
+        Line::styled(format!("Trend:     {rate:+.1} °C/min"), Style::default().fg(color))
     }
 
     fn render_sensor_stats(&self, area: Rect, buf: &mut Buffer) {
-        let title_str = common::title_str_with_status("Live Temperature", self.sensor.temp_success);
-        let stats_title = common::title_block(&title_str, 1, LABEL_COLOR);
+        let freshness = self.sensor.temp_freshness;
+        let title_str =
+            common::title_str_with_status("Live Temperature", self.sensor.temp_success) + &freshness.suffix();
+        let mut stats_title = common::title_block(&title_str, 1, LABEL_COLOR);
+        // Flash the title while above the Max threshold
+        if self.sensor.above_max && self.t % 2 == 0 {
+            stats_title = stats_title.title_style(Style::default().bg(ALERT_COLOR));
+        }
         let inner = stats_title.inner(area);
         stats_title.render(area, buf);
         let [temp_area, gauge_area] = common::area_split(inner, Direction::Vertical, 50, 50);
@@ -246,7 +794,9 @@ impl<S: Source> Thermal<S> {
             tailwind::RED.c700
         };
         let gauge_percent = (((self.sensor.skin_temp / self.sensor.thresholds.critical) * 100.0) as u16).clamp(0, 100);
-        Paragraph::new(self.create_sensor_stats()).render(temp_area, buf);
+        Paragraph::new(self.create_sensor_stats())
+            .style(freshness.style())
+            .render(temp_area, buf);
         Gauge::default()
             .gauge_style(gauge_color)
             .percent(gauge_percent)
@@ -254,19 +804,41 @@ impl<S: Source> Thermal<S> {
     }
 
     fn create_sensor_thresholds(&self) -> Vec<Line<'static>> {
-        vec![
+        let sample_period = match self.sensor.sample_period {
+            Some(period) => format!("{period} ms"),
+            None => "—".to_string(),
+        };
+        let mut lines = vec![
             Line::raw(format!("Warn:     {} °C", self.sensor.thresholds.warn_high.round())),
             Line::raw(format!("Prochot:  {} °C", self.sensor.thresholds.prochot.round())),
             Line::raw(format!("Critical: {} °C", self.sensor.thresholds.critical.round())),
-        ]
+            Line::raw(format!("Sampling: {sample_period}")),
+        ];
+        if let Some(mismatch) = self.sensor.sample_period_echo.and_then(|echo| echo.mismatch_str(" ms")) {
+            lines.push(Line::styled(
+                format!("Mismatch: {mismatch}"),
+                Style::default().fg(CLAMP_COLOR),
+            ));
+        }
+        lines
     }
 
     fn render_sensor_thresholds(&self, area: Rect, buf: &mut Buffer) {
         let title_str = common::title_str_with_status("Thresholds", self.sensor.thresholds_success);
-        let title = common::title_block(&title_str, 1, LABEL_COLOR);
-        Paragraph::new(self.create_sensor_thresholds())
-            .block(title)
-            .render(area, buf);
+        let title = common::title_block(&title_str, 0, LABEL_COLOR);
+        let inner = title.inner(area);
+        title.render(area, buf);
+
+        // The input only needs its one line, leave the rest to the thresholds
+        let [thresholds_area, input_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(inner);
+        Paragraph::new(self.create_sensor_thresholds()).render(thresholds_area, buf);
+        self.sample_period_input.render(
+            "Set Sample Period ms <ENTER>",
+            self.source.is_read_only(),
+            self.focus.is(1),
+            input_area,
+            buf,
+        );
     }
 
     fn render_fan(&self, area: Rect, buf: &mut Buffer) {
@@ -283,63 +855,243 @@ impl<S: Source> Thermal<S> {
             Span::styled((self.fan.rpm_bounds.max / 2.0).to_string(), Style::default().bold()),
             Span::styled(self.fan.rpm_bounds.max.to_string(), Style::default().bold()),
         ];
+        let (x_axis, x_labels) = self.x_axis(&self.fan.samples);
+        // Commanded speed underneath the tach reading, so lag or a stalled fan stands out
+        let series = if self.source.has_commanded_rpm() {
+            vec![common::GraphSeries {
+                label: "Commanded",
+                color: COMMANDED_COLOR,
+                samples: self.fan.commanded_samples.window(&self.viewport),
+            }]
+        } else {
+            Vec::new()
+        };
         let graph = common::Graph {
-            title: "Fan RPM vs Time".to_string(),
+            title: "Fan RPM vs Time".to_string() + &self.viewport.suffix(),
             color: Color::Blue,
-            samples: self.fan.samples.get(),
-            x_axis: "Time (s)".to_string(),
-            x_bounds: [0.0, 60.0],
-            x_labels: common::time_labels(self.t, MAX_SAMPLES),
+            samples: self.fan.samples.window(&self.viewport),
+            x_axis,
+            x_bounds: self.viewport.x_bounds(),
+            x_labels,
             y_axis: "RPM".to_string(),
             y_bounds: [0.0, self.fan.rpm_bounds.max],
             y_labels,
+            markers: Vec::new(),
+            series,
         };
         common::render_chart(area, buf, graph);
     }
 
     fn create_fan_stats(&self) -> Vec<Line<'static>> {
-        vec![Line::raw(format!(
-            "RPM: {} ({}, {})",
+        let mut lines = vec![Line::raw(format!(
+            "RPM: {} ({}, {}) [{}]",
             self.fan.rpm.round(),
             self.fan.rpm_bounds.min,
-            self.fan.rpm_bounds.max
-        ))]
+            self.fan.rpm_bounds.max,
+            match self.fan.manual {
+                Some(true) => "Manual",
+                Some(false) => "Automatic",
+                None => "Mode unknown",
+            }
+        ))];
+
+        if let Some(commanded) = self.fan.commanded {
+            lines.push(Line::raw(format!("Commanded: {} RPM", commanded.round())));
+            // A seized fan reads next to nothing however hard it's driven
+            if self.fan.is_stalled(&self.stall_detect) {
+                lines.push(Line::styled(
+                    format!(
+                        "Fan stalled? Commanded {} RPM but tach reads {}",
+                        commanded.round(),
+                        self.fan.rpm.round()
+                    ),
+                    Style::default().fg(ALERT_COLOR),
+                ));
+            }
+        }
+
+        if let Some((requested, clamped)) = self.fan.clamped {
+            lines.push(Line::styled(
+                format!("Requested {requested} RPM clamped to {clamped}"),
+                Style::default().fg(CLAMP_COLOR),
+            ));
+        }
+
+        if let Some(mismatch) = self.fan.echo.and_then(|echo| echo.mismatch_str(" RPM")) {
+            lines.push(Line::styled(
+                format!("Mismatch: {mismatch}"),
+                Style::default().fg(CLAMP_COLOR),
+            ));
+        } else if self.fan.echo_pending.is_some() {
+            lines.push(Line::raw("Waiting for the fan to settle before checking the RPM"));
+        }
+
+        if let Some(error) = &self.fan.clear_error {
+            lines.push(Line::styled(
+                format!("Automatic control failed: {error}"),
+                Style::default().fg(ALERT_COLOR),
+            ));
+        }
+        lines
     }
 
     fn render_fan_stats(&self, area: Rect, buf: &mut Buffer) {
-        let title_str = common::title_str_with_status("Live Fan RPM", self.fan.rpm_success && self.fan.bounds_success);
+        let freshness = self.fan.rpm_freshness;
+        let title_str = common::title_str_with_status("Live Fan RPM", self.fan.rpm_success && self.fan.bounds_success)
+            + &freshness.suffix();
         let title = common::title_block(&title_str, 0, LABEL_COLOR);
         let inner = title.inner(area);
         title.render(area, buf);
 
-        let [rpm_area, input_area] = common::area_split(inner, Direction::Vertical, 30, 70);
+        let rpms = self.fan.all_rpms();
+        let [rpm_area, input_area] = common::area_split(inner, Direction::Vertical, 40, 60);
+        let [stats_area, gauges_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(rpms.len() as u16)]).areas(rpm_area);
 
-        Paragraph::new(self.create_fan_stats()).render(rpm_area, buf);
+        Paragraph::new(self.create_fan_stats())
+            .style(freshness.style())
+            .render(stats_area, buf);
+        self.render_fan_gauges(&rpms, gauges_area, buf);
         self.render_fan_rpm_input(input_area, buf);
     }
 
+    // One gauge per fan, labelled with the fan once there's more than one
+    fn render_fan_gauges(&self, rpms: &[Option<f64>], area: Rect, buf: &mut Buffer) {
+        let rows = Layout::vertical(vec![Constraint::Length(1); rpms.len()]).split(area);
+        for (fan, (rpm, row)) in rpms.iter().zip(rows.iter()).enumerate() {
+            let percent = rpm.map_or(0, |rpm| rpm_percent(rpm, &self.fan.rpm_bounds));
+            let gauge_color = match percent {
+                0..50 => tailwind::GREEN.c700,
+                50..75 => tailwind::YELLOW.c700,
+                75..90 => tailwind::ORANGE.c700,
+                _ => tailwind::RED.c700,
+            };
+            let mut gauge = Gauge::default().gauge_style(gauge_color).percent(percent);
+            if rpms.len() > 1 {
+                gauge = gauge.label(match rpm {
+                    Some(rpm) => format!("Fan {fan}: {} RPM", rpm.round()),
+                    None => format!("Fan {fan}: —"),
+                });
+            }
+            gauge.render(*row, buf);
+        }
+    }
+
+    fn create_fan_list(&self) -> Vec<String> {
+        self.fan
+            .all_rpms()
+            .iter()
+            .enumerate()
+            .map(|(fan, rpm)| match rpm {
+                Some(rpm) => format!("Fan {fan}: {} RPM", rpm.round()),
+                None => format!("Fan {fan}: Error reading RPM"),
+            })
+            .collect()
+    }
+
     fn create_fan_levels(&self) -> Vec<Line<'static>> {
-        vec![
-            Line::raw(format!("On:      {} °C", self.fan.state_levels.on.round())),
-            Line::raw(format!("Ramping: {} °C", self.fan.state_levels.ramping.round())),
-            Line::raw(format!("Max:     {} °C", self.fan.state_levels.max.round())),
-        ]
+        let levels = &self.fan.state_levels;
+        let level_line = |label: &str, level: &FanLevel| {
+            Line::raw(common::title_str_with_status(
+                &format!("{label} {} °C", level.temp.round()),
+                level.success,
+            ))
+            .style(level.changed.style())
+        };
+
+        let mut lines = vec![level_line("On:     ", &levels.on)];
+        if levels.off.success {
+            let mut line = level_line("Off:    ", &levels.off);
+            if levels.on.success {
+                line.push_span(format!(
+                    " (hysteresis {} °C)",
+                    (levels.on.temp - levels.off.temp).round()
+                ));
+            }
+            lines.push(line);
+        }
+        lines.push(level_line("Ramping:", &levels.ramping));
+        lines.push(level_line("Max:    ", &levels.max));
+        if let Some(running) = self.fan.running {
+            lines.push(Line::raw(format!(
+                "Fan:     {} (switched {} times)",
+                if running { "On" } else { "Off" },
+                self.fan.switches
+            )));
+        }
+        lines
     }
 
     fn render_fan_levels(&self, area: Rect, buf: &mut Buffer) {
-        let title_str = common::title_str_with_status("Fan State Levels", self.fan.levels_success);
+        let title_str = common::title_str_with_status("Fan State Levels", self.fan.state_levels.success());
         let title = common::title_block(&title_str, 1, LABEL_COLOR);
         Paragraph::new(self.create_fan_levels()).block(title).render(area, buf);
     }
 
     fn render_fan_rpm_input(&self, area: Rect, buf: &mut Buffer) {
-        let width = area.width.max(3) - 3;
-        let scroll = self.rpm_input.visual_scroll(width as usize);
-
-        let input = Paragraph::new(self.rpm_input.value())
-            .style(Style::default())
-            .scroll((0, scroll as u16))
-            .block(Block::bordered().title("Set Fan RPM <ENTER>"));
-        input.render(area, buf);
+        self.rpm_input.render(
+            "Set Fan RPM <ENTER> | Automatic <o>",
+            self.source.is_read_only(),
+            self.focus.is(0),
+            area,
+            buf,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds(min: f64, max: f64) -> FanRpmBounds {
+        FanRpmBounds { min, max }
+    }
+
+    #[test]
+    fn validate_rpm_formats() {
+        assert_eq!(validate_rpm("2500"), Ok(2500.0));
+        assert_eq!(validate_rpm("2500.5"), Ok(2500.5));
+        assert_eq!(validate_rpm("0x9C4"), Ok(2500.0));
+        assert_eq!(validate_rpm("0b100111000100"), Ok(2500.0));
+        assert_eq!(validate_rpm("5000000000"), Ok(5_000_000_000.0));
+        assert!(validate_rpm("0x+5").is_err());
+        assert!(validate_rpm("inf").is_err());
+        assert!(validate_rpm("fast").is_err());
+    }
+
+    #[test]
+    fn clamp_rpm_in_range() {
+        assert_eq!(clamp_rpm(3000.0, &bounds(1000.0, 5000.0)), (3000.0, false));
+    }
+
+    #[test]
+    fn clamp_rpm_below_min() {
+        assert_eq!(clamp_rpm(500.0, &bounds(1000.0, 5000.0)), (1000.0, true));
+    }
+
+    #[test]
+    fn clamp_rpm_above_max() {
+        assert_eq!(clamp_rpm(6000.0, &bounds(1000.0, 5000.0)), (5000.0, true));
+    }
+
+    #[test]
+    fn clamp_rpm_inverted_bounds() {
+        // Bounds the EC got backwards can't be trusted, so the request goes through as is
+        assert_eq!(clamp_rpm(6000.0, &bounds(5000.0, 1000.0)), (6000.0, false));
+    }
+
+    #[test]
+    fn settled_echo_tolerance() {
+        assert!(matches!(settled_echo(3000.0, Ok(2900.0)), common::Echo::Match));
+        assert!(matches!(
+            settled_echo(3000.0, Ok(2800.0)),
+            common::Echo::Mismatch { sent: 3000, read: 2800 }
+        ));
+        // Slow speeds get the minimum tolerance rather than a few RPM
+        assert!(matches!(settled_echo(0.0, Ok(80.0)), common::Echo::Match));
+        assert!(matches!(
+            settled_echo(3000.0, Err(color_eyre::eyre::eyre!("timeout"))),
+            common::Echo::ReadFailed { sent: 3000 }
+        ));
     }
 }
@@ -1,4 +1,8 @@
-use crossterm::event::Event;
+use crate::common;
+use crate::widgets::confirm::Confirm;
+use crate::widgets::palette::Outcome;
+use color_eyre::{Report, Result, eyre::eyre};
+use crossterm::event::{Event, KeyCode, KeyEventKind};
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
@@ -7,31 +11,570 @@ use ratatui::{
     widgets::{Block, Borders, Padding, Paragraph, Widget},
 };
 
+use crate::Source;
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
+
 use crate::app::Module;
 
 const LABEL_COLOR: Color = tailwind::SLATE.c200;
+const DATA_NOT_YET_RETRIEVED_MSG: &str = "Data not yet retrieved";
+
+pub const SVID_DISPLAYPORT: u16 = 0xff01;
+pub const SVID_THUNDERBOLT: u16 = 0x8087;
+
+// How long the UCSI tab highlights a connector change notification
+const EVENT_FLASH: Duration = Duration::from_secs(2);
+
+/// Connector change notification from the PPM
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UcsiEvent {
+    pub connector: u8,
+}
+
+/// An alternate mode supported on a connector
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct AltMode {
+    pub svid: u16,
+    pub vdo: u32,
+}
+
+impl AltMode {
+    fn name(&self) -> &'static str {
+        match self.svid {
+            SVID_DISPLAYPORT => "DisplayPort",
+            SVID_THUNDERBOLT => "Thunderbolt",
+            _ => "Unknown",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DataRole {
+    #[default]
+    Ufp,
+    Dfp,
+}
+
+impl TryFrom<u32> for DataRole {
+    type Error = Report;
+    fn try_from(value: u32) -> Result<Self> {
+        match value {
+            0 => Ok(Self::Ufp),
+            1 => Ok(Self::Dfp),
+            _ => Err(eyre!("Unknown data role")),
+        }
+    }
+}
+
+impl DataRole {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ufp => "UFP",
+            Self::Dfp => "DFP",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PowerRole {
+    #[default]
+    Sink,
+    Source,
+}
+
+impl TryFrom<u32> for PowerRole {
+    type Error = Report;
+    fn try_from(value: u32) -> Result<Self> {
+        match value {
+            0 => Ok(Self::Sink),
+            1 => Ok(Self::Source),
+            _ => Err(eyre!("Unknown power role")),
+        }
+    }
+}
+
+impl PowerRole {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sink => "sink",
+            Self::Source => "source",
+        }
+    }
+}
+
+/// Connection state of a connector, see GET_CONNECTOR_STATUS
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ConnectorStatus {
+    pub connected: bool,
+    pub data_role: DataRole,
+    /// None if the PPM doesn't report it
+    pub power_role: Option<PowerRole>,
+    /// Negotiated VBUS voltage, None while there's no contract or if the PPM doesn't report it
+    pub voltage_mv: Option<u32>,
+}
+
+impl ConnectorStatus {
+    // e.g. "connected, DFP, sink"
+    fn describe(&self) -> String {
+        let mut description = format!(
+            "{}, {}",
+            if self.connected { "connected" } else { "disconnected" },
+            self.data_role.as_str()
+        );
+        if let Some(power_role) = self.power_role {
+            description += &format!(", {}", power_role.as_str());
+        }
+        description
+    }
+}
+
+/// Properties of the cable attached to a connector, see GET_CABLE_PROPERTY
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CableProperty {
+    /// Whether the cable has an e-marker, without one it's assumed to be a passive 3 A cable
+    pub e_marked: bool,
+    pub max_current_ma: u32,
+    pub max_voltage_mv: u32,
+    /// Active cables have signal conditioning, passive ones are plain wire
+    pub active: bool,
+}
+
+impl CableProperty {
+    /// One line summary of the cable
+    pub fn describe(&self) -> String {
+        format!(
+            "{}, {}, {:.1} A / {} V max",
+            if self.e_marked { "e-marked" } else { "not e-marked" },
+            if self.active { "active" } else { "passive" },
+            self.max_current_ma as f64 / 1000.0,
+            self.max_voltage_mv / 1000
+        )
+    }
+}
+
+/// Error information bits from GET_ERROR_STATUS
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ErrorStatus(pub u16);
+
+impl ErrorStatus {
+    const DESCRIPTIONS: [&'static str; 15] = [
+        "Unrecognized command",
+        "Non-existent connector number",
+        "Invalid command specific parameters",
+        "Incompatible connector partner",
+        "CC communication error",
+        "Command unsuccessful due to dead battery condition",
+        "Contract negotiation failure",
+        "Overcurrent",
+        "Undefined",
+        "Port partner rejected swap",
+        "Hard reset",
+        "PPM policy conflict",
+        "Swap rejected",
+        "Reverse current protection",
+        "Set sink path rejected",
+    ];
 
-#[derive(Default)]
-pub struct Ucsi {}
+    /// Human readable description of every error bit that is set
+    pub fn describe(&self) -> String {
+        if self.0 == 0 {
+            return "No error".to_string();
+        }
+
+        (0..u16::BITS as usize)
+            .filter(|bit| self.0 & (1 << bit) != 0)
+            .map(|bit| {
+                Self::DESCRIPTIONS
+                    .get(bit)
+                    .map_or_else(|| format!("Reserved bit {bit}"), |desc| desc.to_string())
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Request Data Object of the negotiated PD contract, see GET_CURRENT_RDO
+///
+/// Decoded as a fixed or variable supply request, the kind chargers use.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rdo(pub u32);
+
+impl Rdo {
+    /// Position (from 1) of the source PDO that was requested
+    pub fn object_position(&self) -> u32 {
+        self.0 >> 28
+    }
+
+    /// Sink will give back current when asked, so the last field is a minimum rather than a maximum
+    pub fn giveback(&self) -> bool {
+        self.0 & (1 << 27) != 0
+    }
+
+    /// Sink wanted more than any source PDO offers
+    pub fn capability_mismatch(&self) -> bool {
+        self.0 & (1 << 26) != 0
+    }
+
+    pub fn operating_current_ma(&self) -> u32 {
+        ((self.0 >> 10) & 0x3ff) * 10
+    }
 
-impl Module for Ucsi {
+    /// Maximum operating current, or minimum with giveback
+    pub fn limit_current_ma(&self) -> u32 {
+        (self.0 & 0x3ff) * 10
+    }
+
+    /// One line summary of the contract
+    pub fn describe(&self) -> String {
+        let mut description = format!(
+            "PDO #{}, {:.2} A operating, {:.2} A {}",
+            self.object_position(),
+            self.operating_current_ma() as f64 / 1000.0,
+            self.limit_current_ma() as f64 / 1000.0,
+            if self.giveback() { "min (giveback)" } else { "max" }
+        );
+        if self.capability_mismatch() {
+            description += ", capability mismatch";
+        }
+        description
+    }
+}
+
+// Data refreshed every update for a single connector
+struct ConnectorState {
+    status: Result<ConnectorStatus>,
+    alt_modes: Result<Vec<AltMode>>,
+    // Only read while connected, there's no contract or cable otherwise
+    rdo: Option<Result<Rdo>>,
+    cable: Option<Result<CableProperty>>,
+}
+
+// Commands sent to the selected connector
+#[derive(Clone, Copy)]
+enum Command {
+    SoftReset,
+    HardReset,
+    RoleSwap,
+}
+
+impl Command {
+    // Name used by --confirm
+    fn name(&self) -> &'static str {
+        match self {
+            Self::SoftReset => "soft-reset",
+            Self::HardReset => "hard-reset",
+            Self::RoleSwap => "role-swap",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Self::SoftReset => "Soft reset",
+            Self::HardReset => "Hard reset",
+            Self::RoleSwap => "Data role swap",
+        }
+    }
+}
+
+pub struct Ucsi<S: Source> {
+    source: S,
+    connector_count: Result<u8>,
+    connectors: Vec<ConnectorState>,
+    selected: u8,
+    // Description and outcome of the last command sent to a connector
+    last_command: Option<(String, bool)>,
+    // Why the last command failed, read back from the PPM after a failure
+    last_error: Option<Result<ErrorStatus>>,
+    events: Option<Receiver<UcsiEvent>>,
+    event_count: usize,
+    last_event: Option<(UcsiEvent, Instant)>,
+    // Names of the commands that ask first, and the one waiting on an answer
+    confirm: Vec<String>,
+    pending: Option<Confirm<(Command, u8)>>,
+}
+
+impl<S: Source> Module for Ucsi<S> {
     fn title(&self) -> &'static str {
         "UCSI Information"
     }
 
-    fn update(&mut self) {}
+    fn update(&mut self) {
+        // Connector count is static, so don't try to update after a successful fetch
+        common::retry_static(&mut self.connector_count, || self.source.get_connector_count());
+
+        let count = *self.connector_count.as_ref().unwrap_or(&0);
+        self.connectors = (0..count)
+            .map(|connector| {
+                let status = self.source.get_connector_status(connector);
+                let connected = status.as_ref().is_ok_and(|status| status.connected);
+                ConnectorState {
+                    status,
+                    alt_modes: self.source.get_alternate_modes(connector),
+                    rdo: connected.then(|| self.source.get_current_rdo(connector)),
+                    cable: connected.then(|| self.source.get_cable_property(connector)),
+                }
+            })
+            .collect();
+    }
+
+    fn handle_event(&mut self, evt: &Event) {
+        let Event::Key(key) = evt else {
+            return;
+        };
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
 
-    fn handle_event(&mut self, _evt: &Event) {}
+        if let Some(pending) = &self.pending {
+            match pending.handle_event(evt) {
+                Outcome::Pending => {}
+                Outcome::Cancel => self.pending = None,
+                Outcome::Run((command, connector)) => {
+                    self.pending = None;
+                    self.run_command(command, connector);
+                }
+            }
+            return;
+        }
+
+        let connector = self.selected;
+        let count = *self.connector_count.as_ref().unwrap_or(&0);
+        match key.code {
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(count.saturating_sub(1)),
+            // Commands would only be refused by the source, so don't bother sending them
+            KeyCode::Char('x' | 'X' | 'd') if self.source.is_read_only() => {}
+            KeyCode::Char('x') => self.request_command(Command::SoftReset, connector),
+            KeyCode::Char('X') => self.request_command(Command::HardReset, connector),
+            KeyCode::Char('d') => self.request_command(Command::RoleSwap, connector),
+            _ => {}
+        }
+    }
 
     fn render(&self, area: Rect, buf: &mut Buffer) {
-        let status_title = title_block("UCSI State");
-        Paragraph::default().block(status_title).render(area, buf);
+        let mut title = common::title_str_with_status("UCSI State", self.is_healthy()) + " | ↑↓ select";
+        if !self.source.is_read_only() {
+            title += " | Soft reset <x> | Hard reset <X> | Swap data role <d>";
+        }
+        let mut status_title = title_block(&title);
+        if let Some((event, at)) = self.last_event
+            && at.elapsed() < EVENT_FLASH
+        {
+            status_title = status_title.title(
+                Line::from(format!(
+                    " {}Connector {} changed ",
+                    if common::ascii_status() { "" } else { "⚡ " },
+                    event.connector
+                ))
+                .fg(tailwind::SLATE.c950)
+                .bg(tailwind::YELLOW.c300)
+                .right_aligned(),
+            );
+        }
+        let lines: Vec<Line<'_>> = self.messages().into_iter().map(Line::raw).collect();
+        Paragraph::new(lines).block(status_title).render(area, buf);
+
+        if let Some(pending) = &self.pending {
+            pending.render(area, buf);
+        }
+    }
+
+    fn has_focus(&self) -> bool {
+        // The prompt needs y/n, which would otherwise be app shortcuts
+        self.pending.is_some()
+    }
+
+    fn poll_notifications(&mut self) -> bool {
+        let Some(events) = &self.events else {
+            return false;
+        };
+
+        let pending: Vec<UcsiEvent> = events.try_iter().collect();
+        let Some(&latest) = pending.last() else {
+            return false;
+        };
+
+        self.event_count += pending.len();
+        self.last_event = Some((latest, Instant::now()));
+        // Refresh straight away rather than waiting for the next poll
+        self.update();
+        true
+    }
+
+    fn commands(&self) -> Vec<(&'static str, KeyCode)> {
+        if self.source.is_read_only() {
+            return Vec::new();
+        }
+        vec![
+            ("Soft reset selected connector", KeyCode::Char('x')),
+            ("Hard reset selected connector", KeyCode::Char('X')),
+            ("Swap data role of selected connector", KeyCode::Char('d')),
+        ]
+    }
+
+    fn raw_values(&self) -> Vec<(String, String)> {
+        let mut values = vec![("Connector count".to_string(), common::raw_value(&self.connector_count))];
+        for (connector, state) in self.connectors.iter().enumerate() {
+            values.push((
+                format!("Connector {connector} status"),
+                common::raw_value(&state.status),
+            ));
+            values.push((
+                format!("Connector {connector} alt modes"),
+                common::raw_value(&state.alt_modes),
+            ));
+            if let Some(rdo) = &state.rdo {
+                values.push((format!("Connector {connector} RDO"), common::raw_value(rdo)));
+            }
+            if let Some(cable) = &state.cable {
+                values.push((format!("Connector {connector} cable"), common::raw_value(cable)));
+            }
+        }
+        values
+    }
+
+    fn copy_value(&self) -> Option<String> {
+        let status = self.connectors.get(self.selected as usize)?.status.as_ref().ok()?;
+        Some(format!("Connector {}: {}", self.selected, status.describe()))
+    }
+
+    fn report(&self) -> String {
+        // Errors are already spelled out in the messages, so the section is never elided
+        common::report_section("Connectors", true, self.messages())
     }
 }
 
-impl Ucsi {
-    pub fn new() -> Self {
-        Self {}
+impl<S: Source> Ucsi<S> {
+    pub fn new(source: S, confirm: Vec<String>) -> Self {
+        let mut inst = Self {
+            source,
+            connector_count: Err(eyre!(DATA_NOT_YET_RETRIEVED_MSG)),
+            connectors: Vec::new(),
+            selected: 0,
+            last_command: None,
+            last_error: None,
+            events: None,
+            event_count: 0,
+            last_event: None,
+            confirm,
+            pending: None,
+        };
+        inst.events = inst.source.subscribe_events();
+
+        inst.update();
+        inst
+    }
+
+    // Run a command, or ask first if it was configured to need confirmation
+    fn request_command(&mut self, command: Command, connector: u8) {
+        if self.confirm.iter().any(|name| name == command.name()) {
+            let message = format!("{} connector {connector}?", command.description());
+            self.pending = Some(Confirm::new(message, (command, connector)));
+        } else {
+            self.run_command(command, connector);
+        }
+    }
+
+    fn run_command(&mut self, command: Command, connector: u8) {
+        let result = match command {
+            Command::SoftReset => self.source.connector_reset(connector, false),
+            Command::HardReset => self.source.connector_reset(connector, true),
+            Command::RoleSwap => self.source.swap_data_role(connector),
+        };
+        self.finish_command(
+            connector,
+            format!("{} connector {connector}", command.description()),
+            result,
+        );
+    }
+
+    // Record the command outcome and refresh so the new connector state shows immediately
+    fn finish_command(&mut self, connector: u8, description: String, result: Result<()>) {
+        self.last_error = result.is_err().then(|| self.source.get_error_status(connector));
+        self.last_command = Some((description, result.is_ok()));
+        self.update();
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.connector_count.is_ok()
+            && self
+                .connectors
+                .iter()
+                .all(|connector| connector.status.is_ok() && connector.alt_modes.is_ok())
+    }
+
+    fn messages(&self) -> Vec<String> {
+        let count = match &self.connector_count {
+            Ok(count) => count,
+            Err(err) => return vec![format!("Error retrieving connector count: {err}")],
+        };
+
+        let mut messages = vec![format!("Connectors: {count}")];
+        messages.push(match (&self.events, &self.last_event) {
+            (None, _) => "Notifications: not supported".to_string(),
+            (Some(_), None) => "Notifications: 0".to_string(),
+            (Some(_), Some((event, _))) => format!(
+                "Notifications: {} (last: connector {})",
+                self.event_count, event.connector
+            ),
+        });
+        if let Some((description, success)) = &self.last_command {
+            messages.push(common::title_str_with_status(
+                &format!("Last command: {description}"),
+                *success,
+            ));
+        }
+        match &self.last_error {
+            Some(Ok(status)) => messages.push(format!("  Error status: {}", status.describe())),
+            Some(Err(err)) => messages.push(format!("  Error status: Error: {err}")),
+            None => {}
+        }
+
+        for (connector, state) in self.connectors.iter().enumerate() {
+            let marker = if connector == self.selected as usize {
+                "▶"
+            } else {
+                " "
+            };
+            messages.push(String::new());
+            messages.push(match &state.status {
+                Ok(status) => format!("{marker} Connector {connector}: {}", status.describe()),
+                Err(err) => format!("{marker} Connector {connector}: Error: {err}"),
+            });
+            // The RDO only says which PDO was picked, the voltage it came with is reported in the status
+            let voltage = state
+                .status
+                .as_ref()
+                .ok()
+                .and_then(|status| status.voltage_mv)
+                .map(|voltage_mv| format!("{:.2} V, ", voltage_mv as f64 / 1000.0))
+                .unwrap_or_default();
+            match &state.rdo {
+                Some(Ok(rdo)) => {
+                    messages.push(format!("  Contract: {voltage}{} (RDO {:#010x})", rdo.describe(), rdo.0))
+                }
+                Some(Err(err)) => messages.push(format!("  Contract: Error: {err}")),
+                None => {}
+            }
+            // A cable rated below the contract is the usual reason a port negotiated less than expected
+            match &state.cable {
+                Some(Ok(cable)) => messages.push(format!("  Cable:    {}", cable.describe())),
+                Some(Err(err)) => messages.push(format!("  Cable:    Error: {err}")),
+                None => {}
+            }
+            messages.push("  Alternate modes:".to_string());
+            match &state.alt_modes {
+                Ok(modes) if modes.is_empty() => messages.push("    None".to_string()),
+                Ok(modes) => messages.extend(
+                    modes
+                        .iter()
+                        .map(|mode| format!("  {:<12} SVID {:#06x}  VDO {:#010x}", mode.name(), mode.svid, mode.vdo)),
+                ),
+                Err(err) => messages.push(format!("    Error: {err}")),
+            }
+        }
+        messages
     }
 }
 
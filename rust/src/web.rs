@@ -0,0 +1,75 @@
+use crate::record::Snapshot;
+use color_eyre::{Result, eyre::eyre};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// A client that connects but never sends its request shouldn't hold up everyone else
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Serves the latest [`Snapshot`] as JSON at `/snapshot.json`, so live readings can be shown in a browser
+///
+/// The server runs on its own thread and only ever sees the JSON handed to [`WebServer::publish`], never the
+/// source, so it can't send anything to the EC. It only listens on loopback unless another address is given, since
+/// anyone who can reach it sees the readings.
+pub struct WebServer {
+    snapshot: Arc<Mutex<String>>,
+}
+
+impl WebServer {
+    /// Start listening on `port` at `address`
+    pub fn start(address: IpAddr, port: u16) -> Result<Self> {
+        let address = SocketAddr::new(address, port);
+        let listener = TcpListener::bind(address).map_err(|e| eyre!("Failed to listen on {address}: {e}"))?;
+        let snapshot = Arc::new(Mutex::new("{}".to_string()));
+
+        let shared = Arc::clone(&snapshot);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let result = stream.and_then(|stream| respond(stream, &shared));
+                if let Err(e) = result {
+                    log::warn!("Web request failed: {e}");
+                }
+            }
+        });
+
+        log::info!("Serving snapshots at http://{address}/snapshot.json");
+        Ok(Self { snapshot })
+    }
+
+    /// Replace the snapshot served to new requests
+    pub fn publish(&self, snapshot: &Snapshot) {
+        *self.snapshot.lock().unwrap() = snapshot.to_json();
+    }
+}
+
+// Answer a single request and close the connection
+fn respond(mut stream: TcpStream, snapshot: &Mutex<String>) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/snapshot.json")) => ("200 OK", "application/json", snapshot.lock().unwrap().clone()),
+        (Some("GET"), _) => (
+            "404 Not Found",
+            "text/plain",
+            "Not found, try /snapshot.json\n".to_string(),
+        ),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Only GET is supported\n".to_string(),
+        ),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+         Cache-Control: no-store\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
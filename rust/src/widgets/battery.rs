@@ -133,7 +133,7 @@ impl StatefulWidget for Battery {
             .bar_gap(0)
             .bar_style(Style::default().fg(color))
             .block(Block::default().borders(Borders::ALL).border_type(BorderType::Double))
-            .bar_width(battery_area.width - 2)
+            .bar_width(battery_area.width.saturating_sub(2))
             .render(battery_area, buf);
 
         let width = tip_area.width / 3;
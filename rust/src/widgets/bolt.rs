@@ -21,10 +21,12 @@ impl Widget for Bolt {
             (0.32, 0.55),
             (0.60, 0.05),
         ];
+        // Shifted right a little, keeping within the original area
+        let x_offset = area.width / 15;
         let area = Rect {
-            x: area.x + area.width / 15,
+            x: area.x + x_offset,
             y: area.y + area.height / 4,
-            width: area.width,
+            width: area.width - x_offset,
             height: area.height / 2,
         };
 
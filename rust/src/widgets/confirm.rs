@@ -0,0 +1,59 @@
+use crate::widgets::palette::Outcome;
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Stylize, palette::tailwind},
+    text::Line,
+    widgets::{Block, Clear, Paragraph, Widget},
+};
+
+/// Yes/no prompt opened over the UI before a command that would be disruptive if sent by mistake
+pub struct Confirm<A> {
+    message: String,
+    action: A,
+}
+
+impl<A: Clone> Confirm<A> {
+    /// Ask `message`, running `action` if confirmed
+    pub fn new(message: impl Into<String>, action: A) -> Self {
+        Self {
+            message: message.into(),
+            action,
+        }
+    }
+
+    /// Handle a key: y or Enter confirms, n or Esc cancels and anything else is ignored
+    pub fn handle_event(&self, evt: &Event) -> Outcome<A> {
+        let Event::Key(key) = evt else {
+            return Outcome::Pending;
+        };
+        if key.kind != KeyEventKind::Press {
+            return Outcome::Pending;
+        }
+
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Enter => Outcome::Run(self.action.clone()),
+            KeyCode::Char('n') | KeyCode::Esc => Outcome::Cancel,
+            _ => Outcome::Pending,
+        }
+    }
+
+    /// Render the prompt centered over `area`
+    pub fn render(&self, area: Rect, buf: &mut Buffer) {
+        let width = (self.message.chars().count() as u16).saturating_add(4).max(30);
+        let [area] = Layout::horizontal([Constraint::Length(width)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Length(5)]).flex(Flex::Center).areas(area);
+
+        let block = Block::bordered().title(" Confirm ").border_style(tailwind::RED.c500);
+        let lines = vec![
+            Line::raw(self.message.as_str()).bold(),
+            Line::raw(""),
+            Line::raw("y confirm | n cancel"),
+        ];
+        Clear.render(area, buf);
+        Paragraph::new(lines).centered().block(block).render(area, buf);
+    }
+}
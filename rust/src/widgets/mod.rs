@@ -1,2 +1,5 @@
 pub mod battery;
 pub mod bolt;
+pub mod confirm;
+pub mod palette;
+pub mod validated_input;
@@ -0,0 +1,122 @@
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Stylize, palette::tailwind},
+    text::Line,
+    widgets::{Block, Clear, Paragraph, Widget},
+};
+use tui_input::{Input, backend::crossterm::EventHandler};
+
+/// What the palette wants done after an event
+pub enum Outcome<A> {
+    /// Still choosing
+    Pending,
+    /// Closed without choosing
+    Cancel,
+    /// Run the chosen command
+    Run(A),
+}
+
+/// Filterable list of named commands, opened over the UI so actions can be found without knowing their keys
+pub struct Palette<A> {
+    input: Input,
+    commands: Vec<(String, A)>,
+    // Index into the filtered list
+    selected: usize,
+}
+
+impl<A: Clone> Palette<A> {
+    /// Create a palette offering `commands`, listed in the given order
+    pub fn new(commands: Vec<(String, A)>) -> Self {
+        Self {
+            input: Input::default(),
+            commands,
+            selected: 0,
+        }
+    }
+
+    /// Handle a key: ↑↓ select, Enter runs, Esc cancels and anything else edits the filter
+    pub fn handle_event(&mut self, evt: &Event) -> Outcome<A> {
+        let Event::Key(key) = evt else {
+            return Outcome::Pending;
+        };
+        if key.kind != KeyEventKind::Press {
+            return Outcome::Pending;
+        }
+
+        match key.code {
+            KeyCode::Esc => return Outcome::Cancel,
+            KeyCode::Enter => {
+                if let Some((_, command)) = self.matches().get(self.selected) {
+                    return Outcome::Run((*command).clone());
+                }
+            }
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(self.matches().len().saturating_sub(1)),
+            _ => {
+                if let Some(changed) = self.input.handle_event(evt)
+                    && changed.value
+                {
+                    self.selected = 0;
+                }
+            }
+        }
+        Outcome::Pending
+    }
+
+    // Commands whose name contains the filter's characters in order, ignoring case and spaces
+    fn matches(&self) -> Vec<&(String, A)> {
+        self.commands
+            .iter()
+            .filter(|(name, _)| fuzzy_match(name, self.input.value()))
+            .collect()
+    }
+
+    /// Render the palette centered over `area`
+    pub fn render(&self, area: Rect, buf: &mut Buffer) {
+        let [area] = Layout::horizontal([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let block = Block::bordered()
+            .title(" Commands | ↑↓ select | Enter run | Esc close ")
+            .border_style(tailwind::AMBER.c500);
+        let inner = block.inner(area);
+        Clear.render(area, buf);
+        block.render(area, buf);
+
+        let [filter_area, list_area] = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner);
+        Line::from(vec!["> ".bold(), self.input.value().into()]).render(filter_area, buf);
+
+        // Scroll just enough to keep the selection visible
+        let height = list_area.height as usize;
+        let skip = (self.selected + 1).saturating_sub(height);
+        let lines: Vec<Line<'_>> = self
+            .matches()
+            .into_iter()
+            .enumerate()
+            .skip(skip)
+            .map(|(index, (name, _))| {
+                if index == self.selected {
+                    Line::from(name.as_str().bold().fg(tailwind::SLATE.c950).bg(tailwind::AMBER.c300))
+                } else {
+                    Line::raw(name.as_str())
+                }
+            })
+            .collect();
+        Paragraph::new(lines).render(list_area, buf);
+    }
+}
+
+fn fuzzy_match(name: &str, filter: &str) -> bool {
+    let mut name = name.chars().flat_map(char::to_lowercase);
+    filter
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .all(|wanted| name.any(|c| c == wanted))
+}
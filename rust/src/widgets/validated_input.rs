@@ -0,0 +1,106 @@
+use crate::common;
+use crossterm::event::Event;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style, Stylize, palette::tailwind},
+    text::Line,
+    widgets::{Paragraph, Widget},
+};
+use tui_input::{Input, backend::crossterm::EventHandler};
+
+const FOCUS_COLOR: Color = tailwind::AMBER.c500;
+
+// Turns the raw text into a value, or a message explaining what's wrong with it
+type Parser<T> = Box<dyn Fn(&str) -> Result<T, String>>;
+
+/// Text input that parses its value on submit, showing why it was rejected instead of sending it on
+pub struct ValidatedInput<T> {
+    input: Input,
+    parse: Parser<T>,
+    error: Option<String>,
+}
+
+impl<T> ValidatedInput<T> {
+    /// Create an input accepting whatever `parse` accepts, its error is shown to the user as is
+    pub fn new(parse: impl Fn(&str) -> Result<T, String> + 'static) -> Self {
+        Self {
+            input: Input::default(),
+            parse: Box::new(parse),
+            error: None,
+        }
+    }
+
+    /// Pass an event on to the input, editing the value clears any error
+    pub fn handle_event(&mut self, evt: &Event) {
+        if let Some(changed) = self.input.handle_event(evt)
+            && changed.value
+        {
+            self.error = None;
+        }
+    }
+
+    /// Parse the value, clearing the input if valid
+    ///
+    /// Invalid input is kept so it can be corrected, and empty input is ignored.
+    pub fn submit(&mut self) -> Option<T> {
+        if self.input.value().trim().is_empty() {
+            return None;
+        }
+
+        match (self.parse)(self.input.value()) {
+            Ok(value) => {
+                self.input.reset();
+                self.error = None;
+                Some(value)
+            }
+            Err(error) => {
+                self.error = Some(error);
+                None
+            }
+        }
+    }
+
+    /// The raw value, for uses other than the main parser
+    pub fn value(&self) -> &str {
+        self.input.value()
+    }
+
+    /// Clear the value and any error, once the caller has accepted the raw value
+    pub fn reset(&mut self) {
+        self.error = None;
+        self.input.reset();
+    }
+
+    /// Flag the input as invalid, for checks only the caller can make
+    pub fn set_error(&mut self, error: impl Into<String>) {
+        self.error = Some(error.into());
+    }
+
+    /// Render the input in a bordered block, with any error on the bottom border
+    ///
+    /// A focused input gets a highlighted border, an unfocused one says how to focus it.
+    pub fn render(&self, title: &str, read_only: bool, focused: bool, area: Rect, buf: &mut Buffer) {
+        let width = area.width.saturating_sub(3);
+        let scroll = self.input.visual_scroll(width as usize);
+
+        let mut block = if focused {
+            common::input_block(title, read_only).border_style(Style::default().fg(FOCUS_COLOR))
+        } else if read_only {
+            common::input_block(title, read_only)
+        } else {
+            common::input_block(&format!("{title} | <TAB> to edit"), read_only)
+        };
+        if let Some(error) = &self.error {
+            block = block
+                .border_style(Style::default().fg(Color::Red))
+                .title_bottom(Line::from(format!(" {error} ")).fg(Color::Red));
+        }
+
+        Paragraph::new(self.input.value())
+            .style(Style::default())
+            .scroll((0, scroll as u16))
+            .block(block)
+            .render(area, buf);
+    }
+}